@@ -66,13 +66,16 @@ pub fn main() -> GameResult {
                 position += 1.0;
 
                 // Draw
-                ctx.gfx.begin_frame().unwrap();
+                // The frame ends when `frame` goes out of scope.
+                let mut frame = ctx.gfx.frame_guard().unwrap();
 
-                let mut canvas =
-                    graphics::Canvas::from_frame(ctx, graphics::Color::from([0.1, 0.2, 0.3, 1.0]));
+                let mut canvas = graphics::Canvas::from_frame(
+                    &frame,
+                    graphics::Color::from([0.1, 0.2, 0.3, 1.0]),
+                );
 
                 let circle = graphics::Mesh::new_circle(
-                    ctx,
+                    &frame,
                     DrawMode::fill(),
                     ggez::glam::Vec2::new(0.0, 0.0),
                     100.0,
@@ -82,20 +85,14 @@ pub fn main() -> GameResult {
                 .unwrap();
                 canvas.draw(&circle, ggez::glam::Vec2::new(position, 380.0));
 
-                canvas.finish(ctx).unwrap();
-                ctx.gfx.end_frame().unwrap();
+                canvas.finish(&mut frame).unwrap();
+                frame.finish().unwrap();
 
-                // reset the mouse delta for the next frame
-                // necessary because it's calculated cumulatively each cycle
-                ctx.mouse.reset_delta();
-
-                // Copy the state of the keyboard into the KeyboardContext and
-                // the mouse into the MouseContext.
+                // Reset the mouse delta and save the keyboard and mouse state.
                 // Not required for this example but important if you want to
                 // use the functions keyboard::is_key_just_pressed/released and
                 // mouse::is_button_just_pressed/released.
-                ctx.keyboard.save_keyboard_state();
-                ctx.mouse.save_mouse_state();
+                ggez::input::end_frame(ctx);
 
                 ggez::timer::yield_now();
             }
@@ -5,6 +5,7 @@
 //! `SoundData` connected to a particular sound channel ready to be played.
 #![cfg(feature = "audio")]
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::io;
 use std::io::Read;
@@ -12,8 +13,8 @@ use std::mem;
 use std::path;
 use std::time;
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use crate::context::Has;
 use crate::error::GameError;
@@ -27,30 +28,367 @@ use crate::filesystem::InternalClone;
 /// of your `Context` object.
 pub struct AudioContext {
     fs: Filesystem,
-    _stream: rodio::OutputStream,
-    stream_handle: rodio::OutputStreamHandle,
+    _stream: Option<rodio::OutputStream>,
+    stream_handle: Option<rodio::OutputStreamHandle>,
+    shared: Arc<SharedState>,
+    pause_on_focus_loss: bool,
 }
 
 impl AudioContext {
     /// Create new `AudioContext`.
+    ///
+    /// This opens the default output device with its default configuration.
+    /// `rodio` doesn't let us choose the buffer size of the output stream, so the
+    /// output latency is whatever the platform's audio backend defaults to.
     pub fn new(fs: &Filesystem) -> GameResult<Self> {
         let (stream, stream_handle) = rodio::OutputStream::try_default().map_err(|_e| {
             GameError::AudioError(String::from(
                 "Could not initialize sound system using default output device (for some reason)",
             ))
         })?;
-        Ok(Self {
+        Ok(Self::with_stream(fs, stream, stream_handle))
+    }
+
+    /// Create new `AudioContext` that outputs at the given sample rate, in Hz,
+    /// and with the given number of channels, e.g. 48 kHz stereo to match a video recording.
+    ///
+    /// If the default output device doesn't support that format, a warning is logged
+    /// and its default configuration is used instead, like with [`new()`](#method.new).
+    pub fn new_with_config(fs: &Filesystem, sample_rate: u32, channels: u16) -> GameResult<Self> {
+        match Self::open_with_config(sample_rate, channels) {
+            Ok((stream, stream_handle)) => Ok(Self::with_stream(fs, stream, stream_handle)),
+            Err(e) => {
+                warn!("{e}; falling back to the default output configuration");
+                Self::new(fs)
+            }
+        }
+    }
+
+    fn open_with_config(
+        sample_rate: u32,
+        channels: u16,
+    ) -> GameResult<(rodio::OutputStream, rodio::OutputStreamHandle)> {
+        use rodio::cpal::traits::{DeviceTrait, HostTrait};
+        let device = rodio::cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| {
+                GameError::AudioError(String::from("No audio output device available"))
+            })?;
+        let config = device
+            .supported_output_configs()
+            .map_err(|e| GameError::AudioError(format!("Could not query the output device: {e}")))?
+            .find(|range| {
+                range.channels() == channels
+                    && range.min_sample_rate().0 <= sample_rate
+                    && sample_rate <= range.max_sample_rate().0
+            })
+            .ok_or_else(|| {
+                GameError::AudioError(format!(
+                    "The output device doesn't support {sample_rate} Hz with {channels} channels"
+                ))
+            })?
+            .with_sample_rate(rodio::cpal::SampleRate(sample_rate));
+        rodio::OutputStream::try_from_device_config(&device, config)
+            .map_err(|e| GameError::AudioError(format!("Could not open the output device: {e}")))
+    }
+
+    fn with_stream(
+        fs: &Filesystem,
+        stream: rodio::OutputStream,
+        stream_handle: rodio::OutputStreamHandle,
+    ) -> Self {
+        let shared = Arc::new(SharedState::default());
+        if let Err(e) = stream_handle.play_raw(LatencyProbe::new(shared.clone())) {
+            warn!("Could not measure the audio output latency: {e}");
+        }
+        Self {
             fs: InternalClone::clone(fs),
-            _stream: stream,
-            stream_handle,
-        })
+            _stream: Some(stream),
+            stream_handle: Some(stream_handle),
+            shared,
+            pause_on_focus_loss: false,
+        }
+    }
+
+    /// Create new `AudioContext`, falling back to a silent one if there is no
+    /// usable output device. The returned `bool` tells whether a device was found.
+    ///
+    /// In a silent context, [`Source`](struct.Source.html)s and
+    /// [`SpatialSource`](struct.SpatialSource.html)s can still be created and used as
+    /// normal, they just never make a sound and never report as playing.
+    pub fn new_optional(fs: &Filesystem) -> (Self, bool) {
+        match Self::new(fs) {
+            Ok(audio) => (audio, true),
+            Err(e) => {
+                warn!("{e}; continuing without sound");
+                let audio = Self {
+                    fs: InternalClone::clone(fs),
+                    _stream: None,
+                    stream_handle: None,
+                    shared: Arc::new(SharedState {
+                        silent: true,
+                        ..SharedState::default()
+                    }),
+                    pause_on_focus_loss: false,
+                };
+                (audio, false)
+            }
+        }
     }
 }
 
 impl AudioContext {
     /// Returns the audio device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the context is silent, see [`has_device()`](#method.has_device),
+    /// which is the default on machines without an output device.
+    /// Use [`try_device()`](#method.try_device) to handle that case.
+    #[deprecated(note = "Use `try_device()`, the context may be silent")]
     pub fn device(&self) -> &rodio::OutputStreamHandle {
-        &self.stream_handle
+        self.try_device().unwrap()
+    }
+
+    /// Returns the audio device, or an error if the context is silent.
+    pub fn try_device(&self) -> GameResult<&rodio::OutputStreamHandle> {
+        self.stream_handle
+            .as_ref()
+            .ok_or_else(|| GameError::AudioError(String::from("No audio output device available")))
+    }
+
+    /// Returns whether or not the context has an output device,
+    /// see [`new_optional()`](#method.new_optional).
+    pub fn has_device(&self) -> bool {
+        self.stream_handle.is_some()
+    }
+
+    /// Sets the listener that all [`SpatialSource`](struct.SpatialSource.html)s hear
+    /// from, unless they were given their own ears with
+    /// [`SpatialSource::set_ears()`](struct.SpatialSource.html#method.set_ears).
+    ///
+    /// This is usually the camera: `forward` is the direction it looks at and `up` points
+    /// to the top of the screen. The ears are placed one unit to either side of `position`,
+    /// like the default ears of a `SpatialSource`. Sources pick up the change the next
+    /// time they are played or their position is set.
+    pub fn set_global_listener(
+        &mut self,
+        position: impl Into<mint::Point3<f32>>,
+        forward: impl Into<mint::Vector3<f32>>,
+        up: impl Into<mint::Vector3<f32>>,
+    ) {
+        let listener = Listener {
+            position: position.into(),
+            forward: forward.into(),
+            up: up.into(),
+        };
+        *self.shared.listener.lock().unwrap() = Some(listener);
+    }
+
+    /// Returns the listener set with [`set_global_listener()`](#method.set_global_listener), if any.
+    pub fn global_listener(&self) -> Option<Listener> {
+        *self.shared.listener.lock().unwrap()
+    }
+
+    /// Removes the global listener, so spatial sources go back to their own ears.
+    pub fn clear_global_listener(&mut self) {
+        *self.shared.listener.lock().unwrap() = None;
+    }
+
+    /// Adds another listener, or moves the one added with the same `id`, e.g. the camera
+    /// of each player in split-screen.
+    ///
+    /// Spatial sources that follow the [global listener](#method.set_global_listener) are
+    /// then heard from whichever of these listeners and the global one is nearest to them.
+    /// A sound can't be mixed for several listeners at once, so a sound between two players
+    /// is only heard from the closer one's point of view.
+    pub fn add_listener(&mut self, id: u32, listener: Listener) {
+        let _ = self.shared.listeners.lock().unwrap().insert(id, listener);
+    }
+
+    /// Removes the listener added with [`add_listener()`](#method.add_listener) under `id`.
+    pub fn remove_listener(&mut self, id: u32) {
+        let _ = self.shared.listeners.lock().unwrap().remove(&id);
+    }
+
+    /// Returns the listeners added with [`add_listener()`](#method.add_listener), by id.
+    pub fn listeners(&self) -> Vec<(u32, Listener)> {
+        let listeners = self.shared.listeners.lock().unwrap();
+        listeners
+            .iter()
+            .map(|(&id, &listener)| (id, listener))
+            .collect()
+    }
+
+    /// Returns the time on the shared music clock, which sources attached to it with
+    /// [`SoundSource::set_music_clock()`](trait.SoundSource.html#method.set_music_clock)
+    /// also report as their [`elapsed()`](trait.SoundSource.html#tymethod.elapsed) time.
+    ///
+    /// The clock follows the play time of the first source that was attached. When that
+    /// source is detached or dropped, the next attached source takes over, so the clock
+    /// keeps going as long as any layer is attached; it reads zero while none is.
+    pub fn music_clock(&self) -> time::Duration {
+        self.shared.music_clock()
+    }
+
+    /// Plays a short moment of silence to get the audio device going.
+    ///
+    /// Some platforms only fully start the output stream when the first sound plays,
+    /// which makes that sound late. Call this once at startup, e.g. right after creating
+    /// the context, so the first real sound plays on time. Does nothing without a device.
+    pub fn prewarm(&self) -> GameResult {
+        use rodio::Source;
+        if let Some(device) = &self.stream_handle {
+            let silence = rodio::source::Zero::<f32>::new(2, 44_100)
+                .take_duration(time::Duration::from_millis(50));
+            device
+                .play_raw(silence)
+                .map_err(|e| GameError::AudioError(format!("Could not prewarm the device: {e}")))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the audio section of [`Context::debug_dump()`](crate::Context::debug_dump).
+    pub(crate) fn write_debug_dump(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        use rodio::cpal::traits::{DeviceTrait, HostTrait};
+        writeln!(out, "[audio]")?;
+        if !self.has_device() {
+            return writeln!(out, "  device: none");
+        }
+        let host = rodio::cpal::default_host();
+        let device = host
+            .default_output_device()
+            .and_then(|device| device.name().ok())
+            .unwrap_or_else(|| String::from("unknown"));
+        writeln!(out, "  host: {}", host.id().name())?;
+        writeln!(out, "  default output device: {device}")?;
+        writeln!(out, "  output latency: {:?}", self.output_latency())
+    }
+
+    /// Creates a new sink on the audio device, or one that goes nowhere
+    /// if the context is silent.
+    fn new_sink(&self) -> GameResult<rodio::Sink> {
+        match self.stream_handle {
+            Some(ref device) => Ok(rodio::Sink::try_new(device)?),
+            None => Ok(rodio::Sink::new_idle().0),
+        }
+    }
+
+    /// Creates a new spatial sink on the audio device, or one that goes nowhere
+    /// if the context is silent.
+    fn new_spatial_sink(
+        &self,
+        emitter_position: mint::Point3<f32>,
+        left_ear: mint::Point3<f32>,
+        right_ear: mint::Point3<f32>,
+    ) -> GameResult<SpatialSink> {
+        match self.stream_handle {
+            Some(ref device) => Ok(SpatialSink::Device(rodio::SpatialSink::try_new(
+                device,
+                emitter_position.into(),
+                left_ear.into(),
+                right_ear.into(),
+            )?)),
+            None => Ok(SpatialSink::Silent(rodio::Sink::new_idle().0)),
+        }
+    }
+
+    /// Returns whether or not all sources are paused while the window is unfocused.
+    pub fn pause_on_focus_loss(&self) -> bool {
+        self.pause_on_focus_loss
+    }
+
+    /// Sets whether or not all sources should be paused while the window is unfocused.
+    /// They continue playing once it regains focus.
+    ///
+    /// Defaults to `false`.
+    pub fn set_pause_on_focus_loss(&mut self, pause: bool) {
+        self.pause_on_focus_loss = pause;
+        if !pause {
+            self.shared.focus_paused.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Pauses all sources created from this context, including detached ones,
+    /// e.g. for a pause menu.
+    ///
+    /// This is independent of [`SoundSource::pause()`](trait.SoundSource.html#tymethod.pause):
+    /// sources paused individually stay paused after [`resume_all()`](#method.resume_all).
+    pub fn pause_all(&mut self) {
+        self.shared.all_paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resumes all sources paused by [`pause_all()`](#method.pause_all).
+    pub fn resume_all(&mut self) {
+        self.shared.all_paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Starts playing all of the given sources together, in sync down to the audio frame.
+    ///
+    /// Calling [`play()`](trait.SoundSource.html#method.play) on each source one after the
+    /// other lets them drift apart by however long it takes to set each one up; here all
+    /// sources are restarted first and held silent, then released at once, which is what
+    /// layered music needs to stay aligned.
+    ///
+    /// If any of the sources can't be played, none of them are and the error is returned.
+    pub fn play_synced(&self, sources: &mut [&mut Source]) -> GameResult {
+        let held = Arc::new(AtomicBool::new(true));
+        let mut res = Ok(());
+        for i in 0..sources.len() {
+            res = sources[i]
+                .stop(self)
+                .and_then(|()| sources[i].append(Some(held.clone())));
+            if res.is_err() {
+                // don't leave the sources started so far playing silence forever
+                for source in &mut sources[..i] {
+                    let _ = source.stop(self);
+                }
+                break;
+            }
+        }
+        held.store(false, Ordering::SeqCst);
+        res
+    }
+
+    /// Stops all sounds created from this context at once, including detached ones and
+    /// sounds queued with [`play_later()`](trait.SoundSource.html#tymethod.play_later),
+    /// e.g. on a scene transition.
+    ///
+    /// Sources can be played again afterwards. Like pausing, this takes effect within a
+    /// few milliseconds.
+    pub fn stop_all(&mut self) {
+        let _ = self.shared.stop_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns an estimate of the output latency, the time from a sound being mixed to it
+    /// reaching the speakers, e.g. to delay visuals in a rhythm game to match the audio.
+    ///
+    /// `rodio` doesn't tell us the buffer size of the output stream, so this is measured
+    /// instead, by how much audio the stream pulls in at once. It doesn't include any
+    /// latency added after the audio backend, e.g. by the sound card or Bluetooth
+    /// headphones, so games should still let players calibrate an offset on top of it.
+    ///
+    /// Returns `None` in a silent context, and until the first few audio buffers
+    /// have been played.
+    pub fn output_latency(&self) -> Option<time::Duration> {
+        match self.shared.output_latency.load(Ordering::SeqCst) {
+            0 => None,
+            micros => Some(time::Duration::from_micros(micros)),
+        }
+    }
+
+    /// Returns whether or not all sources are paused by [`pause_all()`](#method.pause_all).
+    pub fn all_paused(&self) -> bool {
+        self.shared.all_paused.load(Ordering::SeqCst)
+    }
+
+    /// Notifies the audio context that the window gained or lost focus.
+    ///
+    /// This is called for you by [`event::process_event()`](../event/fn.process_event.html).
+    pub fn handle_focus(&mut self, gained: bool) {
+        if self.pause_on_focus_loss {
+            self.shared.focus_paused.store(!gained, Ordering::SeqCst);
+        }
     }
 }
 
@@ -60,10 +398,654 @@ impl fmt::Debug for AudioContext {
     }
 }
 
+/// Where spatial sounds are heard from, see
+/// [`AudioContext::set_global_listener()`](struct.AudioContext.html#method.set_global_listener).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Listener {
+    /// The position of the listener.
+    pub position: mint::Point3<f32>,
+    /// The direction the listener faces.
+    pub forward: mint::Vector3<f32>,
+    /// The direction of the top of the listener's head.
+    pub up: mint::Vector3<f32>,
+}
+
+impl Listener {
+    /// Returns the positions of the left and right ear.
+    pub fn ears(&self) -> (mint::Point3<f32>, mint::Point3<f32>) {
+        let position = glam::Vec3::from(self.position);
+        let right = glam::Vec3::from(self.forward)
+            .cross(glam::Vec3::from(self.up))
+            .normalize_or_zero();
+        ((position - right).into(), (position + right).into())
+    }
+}
+
+/// Playback state shared between an `AudioContext` and all of its sources.
+#[derive(Debug, Default)]
+pub(crate) struct SharedState {
+    focus_paused: AtomicBool,
+    all_paused: AtomicBool,
+    listener: Mutex<Option<Listener>>,
+    /// More listeners, see [`AudioContext::add_listener()`].
+    listeners: Mutex<BTreeMap<u32, Listener>>,
+    /// Incremented by [`AudioContext::stop_all()`]; sounds queued before that end.
+    stop_generation: AtomicU64,
+    /// The output latency measured by the `LatencyProbe`, in microseconds, or 0 if unknown.
+    output_latency: AtomicU64,
+    /// The play times of the sources attached to the music clock, in the order they were
+    /// attached. The first one that is still alive drives the clock.
+    music_clock: Mutex<Vec<std::sync::Weak<AtomicU64>>>,
+    /// Set if there is no output device; sources don't queue anything then.
+    silent: bool,
+}
+
+impl SharedState {
+    fn paused(&self) -> bool {
+        self.focus_paused.load(Ordering::SeqCst) || self.all_paused.load(Ordering::SeqCst)
+    }
+
+    /// Returns the global listener or added listener closest to `position`.
+    fn nearest_listener(&self, position: mint::Point3<f32>) -> Option<Listener> {
+        let position = glam::Vec3::from(position);
+        let distance = |listener: &Listener| glam::Vec3::from(listener.position).distance(position);
+        let global = *self.listener.lock().unwrap();
+        let listeners = self.listeners.lock().unwrap();
+        global
+            .iter()
+            .chain(listeners.values())
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .copied()
+    }
+
+    fn music_clock(&self) -> time::Duration {
+        let mut attached = self.music_clock.lock().unwrap();
+        // forget the sources that were dropped
+        attached.retain(|play_time| play_time.strong_count() > 0);
+        let play_time = attached.first().and_then(std::sync::Weak::upgrade);
+        let mus = play_time.map_or(0, |play_time| play_time.load(Ordering::SeqCst));
+        time::Duration::from_micros(mus)
+    }
+}
+
+/// How often sources check whether the `AudioContext` wants them to be paused.
+const SHARED_STATE_INTERVAL: time::Duration = time::Duration::from_millis(5);
+
+/// Wraps a sound so that it follows the pause state of its `AudioContext`,
+/// and ends when the context stops all sounds.
+fn with_shared_state<S>(
+    sound: S,
+    shared: Arc<SharedState>,
+) -> impl rodio::Source<Item = S::Item> + Send + 'static
+where
+    S: rodio::Source + Send + 'static,
+    S::Item: rodio::Sample + Send,
+{
+    use rodio::Source;
+    let generation = shared.stop_generation.load(Ordering::SeqCst);
+    sound
+        .pausable(false)
+        .stoppable()
+        .periodic_access(SHARED_STATE_INTERVAL, move |sound| {
+            if shared.stop_generation.load(Ordering::SeqCst) != generation {
+                sound.stop();
+            }
+            sound.inner_mut().set_paused(shared.paused());
+        })
+}
+
+/// Repeats a sound by decoding it anew for every pass, and flags `ended` when a pass
+/// ends early: before the `total_duration` the decoder reported, or with fewer samples
+/// than the first pass. `rodio` doesn't tell us the actual decoder error.
+///
+/// `rodio`'s own `Repeat` replays a buffered copy of the first pass instead,
+/// which hides a decoder that stops early.
+struct DecodeRepeat {
+    data: SourceData,
+    decoder: rodio::Decoder<EofWatch>,
+    /// Set when the decoder's reader ran out of data.
+    eof: Arc<AtomicBool>,
+    /// `rodio` plays WAV samples missing from truncated data as silence instead
+    /// of ending the sound, so reading past the end is the only sign of that.
+    is_wav: bool,
+    /// Whether or not this pass already read past the end of WAV data.
+    truncated: bool,
+    /// Samples produced in this pass.
+    samples: u64,
+    /// Samples a complete pass has.
+    expected: Option<u64>,
+    ended: Arc<AtomicBool>,
+}
+
+impl DecodeRepeat {
+    fn new(data: SourceData, ended: Arc<AtomicBool>) -> GameResult<Self> {
+        let mut magic = [0; 12];
+        let is_wav = data.reader()?.read_exact(&mut magic).is_ok()
+            && &magic[..4] == b"RIFF"
+            && &magic[8..] == b"WAVE";
+        let eof = Arc::new(AtomicBool::new(false));
+        let decoder = data.watched_decoder(eof.clone())?;
+        let expected = rodio::Source::total_duration(&decoder).map(|duration| {
+            let frames = duration.as_secs_f64() * f64::from(rodio::Source::sample_rate(&decoder));
+            frames.round() as u64 * u64::from(rodio::Source::channels(&decoder))
+        });
+        Ok(DecodeRepeat {
+            data,
+            decoder,
+            eof,
+            is_wav,
+            truncated: false,
+            samples: 0,
+            expected,
+            ended,
+        })
+    }
+
+    /// Starts the next pass, returning `false` if the sound can't be decoded anymore.
+    fn restart(&mut self) -> bool {
+        self.eof.store(false, Ordering::SeqCst);
+        match self.data.watched_decoder(self.eof.clone()) {
+            Ok(decoder) => {
+                self.decoder = decoder;
+                self.truncated = false;
+                self.samples = 0;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+impl Iterator for DecodeRepeat {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        loop {
+            if let Some(sample) = self.decoder.next() {
+                if self.is_wav && !self.truncated && self.eof.load(Ordering::Relaxed) {
+                    self.truncated = true;
+                    self.ended.store(true, Ordering::SeqCst);
+                }
+                self.samples += 1;
+                return Some(sample);
+            }
+
+            let expected = *self.expected.get_or_insert(self.samples);
+            // allow for the last frame getting lost to rounding in `total_duration`
+            let frame = u64::from(rodio::Source::channels(&self.decoder));
+            if self.samples + frame < expected {
+                self.ended.store(true, Ordering::SeqCst);
+            }
+            if self.samples == 0 || !self.restart() {
+                self.ended.store(true, Ordering::SeqCst);
+                return None;
+            }
+        }
+    }
+}
+
+impl rodio::Source for DecodeRepeat {
+    fn current_frame_len(&self) -> Option<usize> {
+        match self.decoder.current_frame_len() {
+            // the next pass has the same format
+            Some(0) => None,
+            len => len,
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.decoder.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.decoder.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+/// Passes reads through and flags when the reader runs out of data.
+struct EofWatch {
+    reader: SoundReader,
+    eof: Arc<AtomicBool>,
+}
+
+impl Read for EofWatch {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            self.eof.store(true, Ordering::Relaxed);
+        }
+        Ok(n)
+    }
+}
+
+impl io::Seek for EofWatch {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+/// Counts the samples played from a sound and publishes the time they
+/// took to play, in microseconds, every `query_interval`.
+struct SampleCounter<S> {
+    sound: S,
+    play_time: Arc<AtomicU64>,
+    query_interval: time::Duration,
+    /// Playing time of all samples before the current format, in microseconds.
+    base_mus: u64,
+    /// Samples played in the current format.
+    samples: u64,
+    /// Samples played in the current format when the time was last published.
+    published_samples: u64,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<S> SampleCounter<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn new(sound: S, play_time: Arc<AtomicU64>, query_interval: time::Duration) -> Self {
+        let channels = sound.channels();
+        let sample_rate = sound.sample_rate();
+        SampleCounter {
+            sound,
+            play_time,
+            query_interval,
+            base_mus: 0,
+            samples: 0,
+            published_samples: 0,
+            channels,
+            sample_rate,
+        }
+    }
+
+    fn samples_per_second(&self) -> u64 {
+        (u64::from(self.channels) * u64::from(self.sample_rate)).max(1)
+    }
+
+    fn elapsed_mus(&self) -> u64 {
+        self.base_mus + self.samples * 1_000_000 / self.samples_per_second()
+    }
+
+    fn publish(&mut self) {
+        let delta = self.elapsed_mus() - self.published_mus();
+        self.published_samples = self.samples;
+        let _ = self.play_time.fetch_add(delta, Ordering::SeqCst);
+    }
+
+    /// The time published so far by this counter.
+    fn published_mus(&self) -> u64 {
+        self.base_mus + self.published_samples * 1_000_000 / self.samples_per_second()
+    }
+}
+
+impl<S> Iterator for SampleCounter<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let (channels, sample_rate) = (self.sound.channels(), self.sound.sample_rate());
+        if (channels, sample_rate) != (self.channels, self.sample_rate) {
+            // the format changed; bank the time played so far and start counting anew
+            self.publish();
+            self.base_mus = self.elapsed_mus();
+            self.samples = 0;
+            self.published_samples = 0;
+            self.channels = channels;
+            self.sample_rate = sample_rate;
+        }
+
+        let sample = self.sound.next();
+        if sample.is_some() {
+            self.samples += 1;
+            let interval = (self.query_interval.as_secs_f64() * self.samples_per_second() as f64)
+                .max(1.0) as u64;
+            if self.samples - self.published_samples >= interval {
+                self.publish();
+            }
+        } else {
+            self.publish();
+        }
+        sample
+    }
+}
+
+impl<S> rodio::Source for SampleCounter<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.sound.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.sound.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.sound.total_duration()
+    }
+}
+
+/// Plays silence instead of a sound until `held` is cleared, so that several
+/// sounds can be started on the same sample. Only starts on frame boundaries
+/// to keep the channels of the sound in place.
+struct SyncStart<S> {
+    sound: S,
+    held: Option<Arc<AtomicBool>>,
+    /// Position of the next silent sample within its frame.
+    frame_pos: u16,
+}
+
+impl<S> SyncStart<S> {
+    fn new(sound: S, held: Option<Arc<AtomicBool>>) -> Self {
+        SyncStart {
+            sound,
+            held,
+            frame_pos: 0,
+        }
+    }
+}
+
+impl<S> Iterator for SyncStart<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        if let Some(held) = &self.held {
+            if self.frame_pos != 0 || held.load(Ordering::SeqCst) {
+                self.frame_pos += 1;
+                if self.frame_pos >= self.sound.channels() {
+                    self.frame_pos = 0;
+                }
+                return Some(rodio::Sample::zero_value());
+            }
+            self.held = None;
+        }
+        self.sound.next()
+    }
+}
+
+impl<S> rodio::Source for SyncStart<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        match self.held {
+            // report the silence as frames of its own, so the sound's frames line up after it
+            Some(_) => Some(usize::from(self.sound.channels().max(1))),
+            None => self.sound.current_frame_len(),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.sound.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.sound.total_duration()
+    }
+}
+
+/// The sample rate of the `LatencyProbe`.
+const PROBE_SAMPLE_RATE: u32 = 48_000;
+/// How many samples the `LatencyProbe` is pulled for between looking at the clock.
+const PROBE_CHECK_INTERVAL: u32 = 32;
+/// The longest pause between samples that still counts as the same output buffer.
+const PROBE_BUFFER_GAP: time::Duration = time::Duration::from_millis(1);
+
+/// An endless silent sound that measures the output latency. The output stream fills
+/// a whole buffer at a time, pulling samples in a quick burst and then waiting until that
+/// buffer is played; the length of a burst is how far ahead of the speakers the mix is.
+struct LatencyProbe {
+    shared: Arc<SharedState>,
+    last_check: Option<time::Instant>,
+    unchecked: u32,
+    burst: u32,
+}
+
+impl LatencyProbe {
+    fn new(shared: Arc<SharedState>) -> Self {
+        LatencyProbe {
+            shared,
+            last_check: None,
+            unchecked: 0,
+            burst: 0,
+        }
+    }
+}
+
+impl Iterator for LatencyProbe {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.unchecked += 1;
+        if self.unchecked >= PROBE_CHECK_INTERVAL {
+            let now = time::Instant::now();
+            if let Some(last_check) = self.last_check {
+                if now - last_check > PROBE_BUFFER_GAP {
+                    // a new buffer started, so the last one is complete
+                    let micros = u64::from(self.burst) * 1_000_000 / u64::from(PROBE_SAMPLE_RATE);
+                    self.shared.output_latency.store(micros, Ordering::SeqCst);
+                    self.burst = 0;
+                }
+            }
+            self.burst += self.unchecked;
+            self.unchecked = 0;
+            self.last_check = Some(now);
+        }
+        Some(0.0)
+    }
+}
+
+impl rodio::Source for LatencyProbe {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        PROBE_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+type FinishFn = Box<dyn FnOnce() + Send>;
+
+/// A function to call once a source finishes playing, shared with the sounds it plays.
+#[derive(Clone, Default)]
+struct OnFinish(Arc<Mutex<Option<FinishFn>>>);
+
+impl OnFinish {
+    fn fire(&self) {
+        let f = self.0.lock().ok().and_then(|mut f| f.take());
+        if let Some(f) = f {
+            f();
+        }
+    }
+}
+
+impl fmt::Debug for OnFinish {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<OnFinish: {self:p}>")
+    }
+}
+
+/// Calls a source's `OnFinish` function when the wrapped sound ends.
+struct FinishHook<S> {
+    sound: S,
+    on_finish: OnFinish,
+}
+
+impl<S> Iterator for FinishHook<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.sound.next();
+        if sample.is_none() {
+            self.on_finish.fire();
+        }
+        sample
+    }
+}
+
+impl<S> rodio::Source for FinishHook<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.sound.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.sound.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.sound.total_duration()
+    }
+}
+
+/// Where to load [`SoundData`](struct.SoundData.html) from; see
+/// [`SoundData::load()`](struct.SoundData.html#method.load).
+///
+/// Paths are resolved through the [`Filesystem`](../filesystem/struct.Filesystem.html),
+/// byte slices (such as the ones returned by `include_bytes!`) are copied,
+/// and readers are read to the end.
+pub enum SoundSourceSpec<'a> {
+    /// A path in the game's filesystem.
+    Path(path::PathBuf),
+    /// Sound data already in memory.
+    Bytes(&'a [u8]),
+    /// Any reader, like an opened file or a network stream.
+    Reader(Box<dyn Read + 'a>),
+}
+
+impl<'a> SoundSourceSpec<'a> {
+    /// Creates a `SoundSourceSpec` reading from the given reader.
+    pub fn reader<R: Read + 'a>(reader: R) -> Self {
+        SoundSourceSpec::Reader(Box::new(reader))
+    }
+}
+
+impl<'a> fmt::Debug for SoundSourceSpec<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SoundSourceSpec::Path(path) => write!(f, "SoundSourceSpec::Path({path:?})"),
+            SoundSourceSpec::Bytes(bytes) => {
+                write!(f, "SoundSourceSpec::Bytes({} bytes)", bytes.len())
+            }
+            SoundSourceSpec::Reader(_) => write!(f, "SoundSourceSpec::Reader"),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for SoundSourceSpec<'a> {
+    fn from(path: &'a str) -> Self {
+        SoundSourceSpec::Path(path.into())
+    }
+}
+
+impl<'a> From<&'a path::Path> for SoundSourceSpec<'a> {
+    fn from(path: &'a path::Path) -> Self {
+        SoundSourceSpec::Path(path.into())
+    }
+}
+
+impl<'a> From<path::PathBuf> for SoundSourceSpec<'a> {
+    fn from(path: path::PathBuf) -> Self {
+        SoundSourceSpec::Path(path)
+    }
+}
+
+impl<'a> From<&'a [u8]> for SoundSourceSpec<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        SoundSourceSpec::Bytes(bytes)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for SoundSourceSpec<'a> {
+    fn from(bytes: &'a [u8; N]) -> Self {
+        SoundSourceSpec::Bytes(bytes)
+    }
+}
+
+/// An audio file format, see [`SoundData::with_format_hint()`](struct.SoundData.html#method.with_format_hint).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AudioFormat {
+    /// WAV
+    Wav,
+    /// Ogg Vorbis
+    Vorbis,
+    /// FLAC
+    Flac,
+    /// MP3, only supported with the `mp3` feature
+    Mp3,
+}
+
+/// Creates a decoder for the given format, or guesses the format if it's `None`.
+fn new_decoder<R>(
+    reader: R,
+    format: Option<AudioFormat>,
+) -> Result<rodio::Decoder<R>, rodio::decoder::DecoderError>
+where
+    R: Read + io::Seek + Send + Sync + 'static,
+{
+    match format {
+        None => rodio::Decoder::new(reader),
+        Some(AudioFormat::Wav) => rodio::Decoder::new_wav(reader),
+        Some(AudioFormat::Vorbis) => rodio::Decoder::new_vorbis(reader),
+        Some(AudioFormat::Flac) => rodio::Decoder::new_flac(reader),
+        #[cfg(feature = "mp3")]
+        Some(AudioFormat::Mp3) => rodio::Decoder::new_mp3(reader),
+        #[cfg(not(feature = "mp3"))]
+        Some(AudioFormat::Mp3) => Err(rodio::decoder::DecoderError::UnrecognizedFormat),
+    }
+}
+
 /// Static sound data stored in memory.
 /// It is `Arc`'ed, so cheap to clone.
 #[derive(Clone, Debug)]
-pub struct SoundData(Arc<[u8]>);
+pub struct SoundData {
+    data: Arc<[u8]>,
+    format: Option<AudioFormat>,
+}
 
 impl SoundData {
     /// Load the file at the given path and create a new `SoundData` from it.
@@ -74,9 +1056,46 @@ impl SoundData {
         SoundData::from_read(file)
     }
 
+    /// Loads a new `SoundData` from a path, a byte slice or a reader.
+    ///
+    /// ```rust,no_run
+    /// # use ggez::{audio::{SoundData, SoundSourceSpec}, Context, GameResult};
+    /// # fn t(ctx: &Context) -> GameResult {
+    /// let from_file = SoundData::load(ctx, "/sound.ogg")?;
+    /// let embedded = SoundData::load(ctx, include_bytes!("../resources/sound.ogg"))?;
+    /// let from_reader = SoundData::load(ctx, SoundSourceSpec::reader(std::io::empty()))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load<'a>(
+        fs: &impl Has<Filesystem>,
+        source: impl Into<SoundSourceSpec<'a>>,
+    ) -> GameResult<Self> {
+        match source.into() {
+            SoundSourceSpec::Path(path) => SoundData::new(fs, path),
+            SoundSourceSpec::Bytes(bytes) => Ok(SoundData::from_bytes(bytes)),
+            SoundSourceSpec::Reader(mut reader) => SoundData::from_read(&mut reader),
+        }
+    }
+
     /// Copies the data in the given slice into a new `SoundData` object.
     pub fn from_bytes(data: &[u8]) -> Self {
-        SoundData(Arc::from(data))
+        SoundData::from(Arc::from(data))
+    }
+
+    /// Like [`from_bytes()`](#method.from_bytes), but always decodes the data as the given
+    /// format instead of guessing it, for data without a proper header.
+    pub fn with_format_hint(data: &[u8], format: AudioFormat) -> Self {
+        SoundData {
+            data: Arc::from(data),
+            format: Some(format),
+        }
+    }
+
+    /// Returns the format the data is decoded as, if it was given
+    /// with [`with_format_hint()`](#method.with_format_hint).
+    pub fn format_hint(&self) -> Option<AudioFormat> {
+        self.format
     }
 
     /// Creates a `SoundData` from any `Read` object; this involves
@@ -93,34 +1112,63 @@ impl SoundData {
 
     /// Indicates if the data can be played as a sound.
     pub fn can_play(&self) -> bool {
-        let cursor = io::Cursor::new(self.clone());
-        rodio::Decoder::new(cursor).is_ok()
+        self.decoder().is_ok()
+    }
+
+    fn decoder(&self) -> GameResult<rodio::Decoder<io::Cursor<SoundData>>> {
+        Ok(new_decoder(io::Cursor::new(self.clone()), self.format)?)
+    }
+
+    /// Returns the sample rate of the sound, in Hz.
+    pub fn sample_rate(&self) -> GameResult<u32> {
+        Ok(rodio::Source::sample_rate(&self.decoder()?))
+    }
+
+    /// Returns the number of channels of the sound.
+    pub fn channels(&self) -> GameResult<u16> {
+        Ok(rodio::Source::channels(&self.decoder()?))
+    }
+
+    /// Decodes the whole sound, returning its samples, interleaved by channel and
+    /// ranging from `-1.0` to `1.0`, along with the sample rate and number of channels.
+    ///
+    /// This decodes all of the data at once, so it can take a while for long sounds.
+    pub fn to_pcm(&self) -> GameResult<(Vec<f32>, u32, u16)> {
+        use rodio::Source;
+        let decoder = self.decoder()?;
+        let sample_rate = decoder.sample_rate();
+        let channels = decoder.channels();
+        let samples = decoder.convert_samples::<f32>().collect();
+        Ok((samples, sample_rate, channels))
     }
 }
 
 impl From<Arc<[u8]>> for SoundData {
     #[inline]
     fn from(arc: Arc<[u8]>) -> Self {
-        SoundData(arc)
+        SoundData {
+            data: arc,
+            format: None,
+        }
     }
 }
 
 impl From<Vec<u8>> for SoundData {
     fn from(v: Vec<u8>) -> Self {
-        SoundData(Arc::from(v))
+        SoundData::from(Arc::from(v))
     }
 }
 
 impl From<Box<[u8]>> for SoundData {
     fn from(b: Box<[u8]>) -> Self {
-        SoundData(Arc::from(b))
+        SoundData::from(Arc::<[u8]>::from(b))
     }
 }
 
 impl AsRef<[u8]> for SoundData {
     #[inline]
     fn as_ref(&self) -> &[u8] {
-        self.0.as_ref()
+        self.data.as_ref()
     }
 }
 
@@ -194,35 +1242,246 @@ pub trait SoundSource {
     /// Time measurement is based on audio samples consumed, so it may drift from the system
     fn elapsed(&self) -> time::Duration;
 
+    /// Returns the length of the sound data, or `None` if it isn't known.
+    ///
+    /// Not all formats report their length; it is usually known for WAV and FLAC,
+    /// but not for streamed formats like Ogg Vorbis or MP3.
+    /// The default implementation returns `None`.
+    fn duration(&self) -> Option<time::Duration> {
+        None
+    }
+
+    /// Returns an error if a pass of a repeating source ended early since the last call,
+    /// which happens when the sound data can't be decoded past some point, e.g. because
+    /// the file is truncated. The source keeps repeating the part that can be decoded,
+    /// or stops if nothing can be.
+    ///
+    /// This can be used to notice (and maybe replace) broken background music.
+    /// The default implementation never reports an error.
+    fn take_error(&self) -> Option<GameError> {
+        None
+    }
+
+    /// Returns how far playback has progressed through the sound data,
+    /// from `0.0` to `1.0`, or `None` if its [`duration()`](#method.duration) isn't known.
+    ///
+    /// Unlike [`elapsed()`](#tymethod.elapsed) this is a position in the sound data,
+    /// so it includes the time skipped with [`set_start()`](#tymethod.set_start)
+    /// and takes the pitch into account. Repeating sources wrap around to `0.0`.
+    /// The default implementation returns `None`.
+    fn progress(&self) -> Option<f32> {
+        None
+    }
+
     /// Set the update interval of the internal sample counter.
     ///
     /// This parameter determines the precision of the time measured by [`elapsed()`](#method.elapsed).
     fn set_query_interval(&mut self, t: time::Duration);
+
+    /// Attaches the source to the shared music clock of its `AudioContext`, or detaches it.
+    ///
+    /// While attached, [`elapsed()`](#tymethod.elapsed) reports the
+    /// [music clock](struct.AudioContext.html#method.music_clock) instead of the source's
+    /// own play time, so layers of a song that were started a few milliseconds apart all
+    /// agree on where the song is. If no source drives the clock yet, this one does.
+    ///
+    /// The default implementation does nothing.
+    fn set_music_clock(&mut self, _attached: bool) {}
+}
+
+/// Files larger than this many bytes are streamed from disk by
+/// [`Source::new_streaming()`](struct.Source.html#method.new_streaming).
+pub const STREAMING_THRESHOLD: u64 = 1024 * 1024;
+
+/// Where a source reads its sound data from.
+#[derive(Clone, Debug)]
+enum SourceData {
+    Memory(io::Cursor<SoundData>),
+    File(path::PathBuf),
+    Reader(Arc<Mutex<ReceivedData>>),
+}
+
+impl SourceData {
+    /// Returns a decoder for the sound data, starting at its beginning.
+    fn decoder(&self) -> GameResult<rodio::Decoder<SoundReader>> {
+        Ok(new_decoder(self.reader()?, self.format())?)
+    }
+
+    /// Returns a decoder like [`decoder()`](#method.decoder) that sets `eof`
+    /// once it read all of the sound data.
+    fn watched_decoder(&self, eof: Arc<AtomicBool>) -> GameResult<rodio::Decoder<EofWatch>> {
+        let reader = EofWatch {
+            reader: self.reader()?,
+            eof,
+        };
+        Ok(new_decoder(reader, self.format())?)
+    }
+
+    fn format(&self) -> Option<AudioFormat> {
+        match self {
+            SourceData::Memory(cursor) => cursor.get_ref().format,
+            SourceData::File(_) | SourceData::Reader(_) => None,
+        }
+    }
+
+    /// Returns a reader positioned at the start of the sound data.
+    fn reader(&self) -> GameResult<SoundReader> {
+        match self {
+            SourceData::Memory(cursor) => Ok(SoundReader::Memory(cursor.clone())),
+            SourceData::File(path) => Ok(SoundReader::File(io::BufReader::new(
+                std::fs::File::open(path)?,
+            ))),
+            SourceData::Reader(data) => Ok(SoundReader::Reader {
+                data: data.clone(),
+                pos: 0,
+            }),
+        }
+    }
+}
+
+/// The sound data received so far from a reader given to
+/// [`Source::from_reader_streaming()`](struct.Source.html#method.from_reader_streaming).
+/// It is kept around so the sound can be replayed and seeked in.
+struct ReceivedData {
+    data: Vec<u8>,
+    reader: Option<Box<dyn Read + Send>>,
+}
+
+impl ReceivedData {
+    fn shared(reader: impl Read + Send + 'static) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(ReceivedData {
+            data: Vec::new(),
+            reader: Some(Box::new(reader)),
+        }))
+    }
+
+    /// Reads from the reader until at least `len` bytes were received, or it ends.
+    fn fill_to(&mut self, len: u64) -> io::Result<()> {
+        let mut chunk = [0; 8192];
+        while (self.data.len() as u64) < len {
+            let reader = match &mut self.reader {
+                Some(reader) => reader,
+                None => break,
+            };
+            match reader.read(&mut chunk) {
+                Ok(0) => self.reader = None,
+                Ok(n) => self.data.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ReceivedData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<ReceivedData: {} bytes, finished: {}>",
+            self.data.len(),
+            self.reader.is_none()
+        )
+    }
+}
+
+enum SoundReader {
+    Memory(io::Cursor<SoundData>),
+    File(io::BufReader<std::fs::File>),
+    Reader {
+        data: Arc<Mutex<ReceivedData>>,
+        pos: u64,
+    },
+}
+
+impl Read for SoundReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SoundReader::Memory(cursor) => cursor.read(buf),
+            SoundReader::File(file) => file.read(buf),
+            SoundReader::Reader { data, pos } => {
+                let mut data = data.lock().unwrap();
+                data.fill_to(*pos + 1)?;
+                let start = data.data.len().min(*pos as usize);
+                let n = buf.len().min(data.data.len() - start);
+                buf[..n].copy_from_slice(&data.data[start..start + n]);
+                *pos += n as u64;
+                Ok(n)
+            }
+        }
+    }
+}
+
+impl io::Seek for SoundReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match self {
+            SoundReader::Memory(cursor) => cursor.seek(pos),
+            SoundReader::File(file) => file.seek(pos),
+            SoundReader::Reader { data, pos: current } => {
+                let target = match pos {
+                    io::SeekFrom::Start(offset) => Some(offset),
+                    io::SeekFrom::Current(offset) => current.checked_add_signed(offset),
+                    io::SeekFrom::End(offset) => {
+                        let mut data = data.lock().unwrap();
+                        data.fill_to(u64::MAX)?;
+                        (data.data.len() as u64).checked_add_signed(offset)
+                    }
+                };
+                *current = target.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position")
+                })?;
+                Ok(*current)
+            }
+        }
+    }
 }
 
 /// Internal state used by audio sources.
 #[derive(Debug)]
 pub(crate) struct SourceState {
-    data: io::Cursor<SoundData>,
+    data: SourceData,
     repeat: bool,
     fade_in: time::Duration,
     skip_duration: time::Duration,
     speed: f32,
     query_interval: time::Duration,
-    play_time: Arc<AtomicUsize>,
+    play_time: Arc<AtomicU64>,
+    music_clock: bool,
+    shared: Arc<SharedState>,
+    total_duration: Option<time::Duration>,
+    ended_early: Arc<AtomicBool>,
+    on_finish: OnFinish,
 }
 
 impl SourceState {
     /// Create a new `SourceState` based around the given `SoundData`
-    pub fn new(cursor: io::Cursor<SoundData>) -> Self {
+    pub fn new(cursor: io::Cursor<SoundData>, shared: Arc<SharedState>) -> Self {
+        Self::from_source_data(SourceData::Memory(cursor), shared)
+    }
+
+    /// Create a new `SourceState` streaming from the file at the given location on disk
+    fn streaming(path: path::PathBuf, shared: Arc<SharedState>) -> Self {
+        Self::from_source_data(SourceData::File(path), shared)
+    }
+
+    fn from_source_data(data: SourceData, shared: Arc<SharedState>) -> Self {
+        let total_duration = data
+            .decoder()
+            .ok()
+            .and_then(|decoder| rodio::Source::total_duration(&decoder));
         SourceState {
-            data: cursor,
+            data,
             repeat: false,
             fade_in: time::Duration::from_millis(0),
             skip_duration: time::Duration::from_millis(0),
             speed: 1.0,
             query_interval: time::Duration::from_millis(100),
-            play_time: Arc::new(AtomicUsize::new(0)),
+            play_time: Arc::new(AtomicU64::new(0)),
+            music_clock: false,
+            shared,
+            total_duration,
+            ended_early: Arc::new(AtomicBool::new(false)),
+            on_finish: OnFinish::default(),
         }
     }
     /// Sets the source to repeat playback infinitely on next [`play()`](#method.play)
@@ -251,11 +1510,60 @@ impl SourceState {
 
     /// Get the time the source has been playing since the last call to [`play()`](#method.play).
     ///
-    /// Time measurement is based on audio samples consumed, so it may drift from the system
-    /// clock over longer periods of time.
+    /// Time measurement is based on the audio samples actually played, so it doesn't advance
+    /// while the source is paused or the output falls behind, and it may drift from the
+    /// system clock over longer periods of time.
     pub fn elapsed(&self) -> time::Duration {
-        let t = self.play_time.load(Ordering::SeqCst);
-        time::Duration::from_micros(t as u64)
+        if self.music_clock {
+            return self.shared.music_clock();
+        }
+        time::Duration::from_micros(self.play_time.load(Ordering::SeqCst))
+    }
+
+    /// Attaches the source to the shared music clock, or detaches it.
+    pub fn set_music_clock(&mut self, attached: bool) {
+        if attached == self.music_clock {
+            return;
+        }
+        self.music_clock = attached;
+        let mut clock = self.shared.music_clock.lock().unwrap();
+        if attached {
+            clock.push(Arc::downgrade(&self.play_time));
+        } else {
+            clock.retain(|play_time| {
+                !std::ptr::eq(play_time.as_ptr(), Arc::as_ptr(&self.play_time))
+            });
+        }
+    }
+
+    /// Returns an error if a pass of a repeating source ended early since the last call.
+    pub fn take_error(&self) -> Option<GameError> {
+        if self.ended_early.swap(false, Ordering::SeqCst) {
+            Some(GameError::AudioError(String::from(
+                "Repeating source ended a pass early, the sound data could not be fully decoded",
+            )))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the length of the sound data, if known.
+    pub fn duration(&self) -> Option<time::Duration> {
+        self.total_duration
+    }
+
+    /// Returns the playback position relative to the length of the sound data, if known.
+    pub fn progress(&self) -> Option<f32> {
+        let total = self.total_duration?.as_secs_f32();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut position =
+            self.skip_duration.as_secs_f32() + self.elapsed().as_secs_f32() * self.speed;
+        if self.repeat {
+            position %= total;
+        }
+        Some((position / total).clamp(0.0, 1.0))
     }
 
     /// Set the update interval of the internal sample counter.
@@ -264,6 +1572,15 @@ impl SourceState {
     pub fn set_query_interval(&mut self, t: time::Duration) {
         self.query_interval = t;
     }
+
+    /// Wraps a sound so that its played samples are counted towards [`elapsed()`](#method.elapsed).
+    fn count_samples<S>(&self, sound: S) -> SampleCounter<S>
+    where
+        S: rodio::Source,
+        S::Item: rodio::Sample,
+    {
+        SampleCounter::new(sound, self.play_time.clone(), self.query_interval)
+    }
 }
 
 /// A source of audio data that is connected to an output
@@ -293,6 +1610,68 @@ impl Source {
         Source::from_data(audio, data)
     }
 
+    /// Creates a new `Source` from the given file, streaming it from disk if it is large.
+    ///
+    /// Files larger than [`STREAMING_THRESHOLD`](constant.STREAMING_THRESHOLD.html) are
+    /// decoded while playing instead of being loaded into memory first, which is what
+    /// you want for long music tracks. Smaller files, and files inside of zip archives,
+    /// are loaded like with [`new()`](#method.new).
+    pub fn new_streaming<P: AsRef<path::Path>>(
+        ctxs: &impl Has<AudioContext>,
+        path: P,
+    ) -> GameResult<Self> {
+        let audio = ctxs.retrieve();
+        let path = path.as_ref();
+        let physical_path = audio
+            .fs
+            .physical_path(path)
+            .filter(|p| std::fs::metadata(p).is_ok_and(|m| m.len() > STREAMING_THRESHOLD));
+        let physical_path = match physical_path {
+            Some(p) => p,
+            None => return Source::new(audio, path),
+        };
+
+        let state = SourceState::streaming(physical_path, audio.shared.clone());
+        if state.data.decoder().is_err() {
+            return Err(GameError::AudioError(format!(
+                "Could not decode the audio file {path:?}"
+            )));
+        }
+        Ok(Source {
+            sink: audio.new_sink()?,
+            state,
+        })
+    }
+
+    /// Creates a new `Source` that decodes sound data from the given reader while
+    /// playing, e.g. the body of an HTTP response, without waiting for all of it first.
+    ///
+    /// The reader is only read from when the decoder needs more data, which happens on
+    /// the audio thread. If it blocks, all sounds stutter until it returns, so for
+    /// slow transports like the network, read ahead on another thread and hand this
+    /// the buffered end. Everything received is kept in memory, so the source can be
+    /// replayed and [`set_start()`](trait.SoundSource.html#tymethod.set_start) works; formats that need
+    /// to look at the end of the data may read all of it right away.
+    ///
+    /// Enough data to recognize the format is read immediately.
+    pub fn from_reader_streaming(
+        ctxs: &impl Has<AudioContext>,
+        reader: impl Read + Send + 'static,
+    ) -> GameResult<Self> {
+        let audio = ctxs.retrieve();
+        let data = SourceData::Reader(ReceivedData::shared(reader));
+        let state = SourceState::from_source_data(data, audio.shared.clone());
+        if state.data.decoder().is_err() {
+            return Err(GameError::AudioError(String::from(
+                "Could not decode the audio stream",
+            )));
+        }
+        Ok(Source {
+            sink: audio.new_sink()?,
+            state,
+        })
+    }
+
     /// Creates a new `Source` using the given `SoundData` object.
     pub fn from_data(audio: &impl Has<AudioContext>, data: SoundData) -> GameResult<Self> {
         let audio = audio.retrieve();
@@ -301,58 +1680,116 @@ impl Source {
                 "Could not decode the given audio data".to_string(),
             ));
         }
-        let sink = rodio::Sink::try_new(audio.device())?;
+        let sink = audio.new_sink()?;
         let cursor = io::Cursor::new(data);
         Ok(Source {
             sink,
-            state: SourceState::new(cursor),
+            state: SourceState::new(cursor, audio.shared.clone()),
         })
     }
+
+    /// Creates a new `Source` using the given `SoundData`, decoding it as the given format
+    /// instead of guessing it, see [`SoundData::with_format_hint()`](struct.SoundData.html#method.with_format_hint).
+    pub fn from_data_with_hint(
+        audio: &impl Has<AudioContext>,
+        data: SoundData,
+        format: AudioFormat,
+    ) -> GameResult<Self> {
+        let data = SoundData {
+            format: Some(format),
+            ..data
+        };
+        Source::from_data(audio, data)
+    }
 }
 
-impl SoundSource for Source {
-    fn play_later(&self) -> GameResult {
+impl Source {
+    /// Sets a function to call once the source finishes playing.
+    ///
+    /// The function is called a single time, when the sound played by the next
+    /// [`play()`](trait.SoundSource.html#method.play) (or the one currently playing) reaches
+    /// its end; set it again to be notified about later plays as well. It is not called
+    /// when the source is stopped or dropped, nor for repeating sources unless they fail.
+    ///
+    /// Note that it runs on the audio thread, so it should do as little as possible,
+    /// like sending a message over a channel that the game checks in `update()`:
+    ///
+    /// ```rust,no_run
+    /// # use ggez::{audio, audio::SoundSource, GameResult};
+    /// # fn t(ctx: &mut ggez::Context) -> GameResult {
+    /// let (tx, rx) = std::sync::mpsc::channel();
+    /// let mut intro = audio::Source::new(ctx, "/intro.ogg")?;
+    /// intro.on_finish(move || {
+    ///     let _ = tx.send(());
+    /// });
+    /// intro.play(ctx)?;
+    /// // ...later, in update():
+    /// if rx.try_recv().is_ok() {
+    ///     // play the next track
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_finish(&mut self, f: impl FnOnce() + Send + 'static) {
+        *self.state.on_finish.0.lock().unwrap() = Some(Box::new(f));
+    }
+
+    /// Appends the sound to the sink; while `held` is set, silence is played instead.
+    fn append(&self, held: Option<Arc<AtomicBool>>) -> GameResult {
         // Creating a new Decoder each time seems a little messy,
         // since it may do checking and data-type detection that is
         // redundant, but it's not super expensive.
         // See https://github.com/ggez/ggez/issues/98 for discussion
         use rodio::Source;
-        let cursor = self.state.data.clone();
-
-        let counter = self.state.play_time.clone();
-        let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
-            + self.state.query_interval.subsec_micros() as usize;
+        if self.state.shared.silent {
+            // nothing is played, so the sound is over right away
+            self.state.on_finish.fire();
+            return Ok(());
+        }
 
         if self.state.repeat {
-            let sound = rodio::Decoder::new(cursor)?
-                .repeat_infinite()
+            let sound = DecodeRepeat::new(self.state.data.clone(), self.state.ended_early.clone())?
                 .skip_duration(self.state.skip_duration)
                 .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
+                .fade_in(self.state.fade_in);
+            let sound = self.state.count_samples(sound);
+            let sound = FinishHook {
+                sound: SyncStart::new(sound, held),
+                on_finish: self.state.on_finish.clone(),
+            };
+            self.sink
+                .append(with_shared_state(sound, self.state.shared.clone()));
         } else {
-            let sound = rodio::Decoder::new(cursor)?
+            let sound = self
+                .state
+                .data
+                .decoder()?
                 .skip_duration(self.state.skip_duration)
                 .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
+                .fade_in(self.state.fade_in);
+            let sound = FinishHook {
+                sound: SyncStart::new(self.state.count_samples(sound), held),
+                on_finish: self.state.on_finish.clone(),
+            };
+            self.sink
+                .append(with_shared_state(sound, self.state.shared.clone()));
         }
 
         Ok(())
     }
+}
+
+impl SoundSource for Source {
+    fn play_later(&self) -> GameResult {
+        self.append(None)
+    }
 
     fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
         let audio = audio.retrieve();
         self.stop(audio)?;
         self.play_later()?;
 
-        let new_sink = rodio::Sink::try_new(audio.device())?;
+        let new_sink = audio.new_sink()?;
         let old_sink = mem::replace(&mut self.sink, new_sink);
         old_sink.detach();
 
@@ -397,8 +1834,7 @@ impl SoundSource for Source {
         // We also need to carry over information from the previous sink.
         let volume = self.volume();
 
-        let device = audio.device();
-        self.sink = rodio::Sink::try_new(device)?;
+        self.sink = audio.new_sink()?;
         self.state.play_time.store(0, Ordering::SeqCst);
 
         // Restore information from the previous link.
@@ -430,9 +1866,25 @@ impl SoundSource for Source {
         self.state.elapsed()
     }
 
+    fn duration(&self) -> Option<time::Duration> {
+        self.state.duration()
+    }
+
+    fn take_error(&self) -> Option<GameError> {
+        self.state.take_error()
+    }
+
+    fn progress(&self) -> Option<f32> {
+        self.state.progress()
+    }
+
     fn set_query_interval(&mut self, t: time::Duration) {
         self.state.set_query_interval(t)
     }
+
+    fn set_music_clock(&mut self, attached: bool) {
+        self.state.set_music_clock(attached)
+    }
 }
 
 impl fmt::Debug for Source {
@@ -441,14 +1893,105 @@ impl fmt::Debug for Source {
     }
 }
 
+/// The sink of a `SpatialSource`. `rodio` can't create spatial sinks without a device,
+/// so in a silent context a plain sink that goes nowhere stands in for it.
+enum SpatialSink {
+    Device(rodio::SpatialSink),
+    Silent(rodio::Sink),
+}
+
+impl SpatialSink {
+    fn append<S>(&self, sound: S)
+    where
+        S: rodio::Source + Send + 'static,
+        f32: rodio::cpal::FromSample<S::Item>,
+        S::Item: rodio::Sample + Send,
+    {
+        if let SpatialSink::Device(sink) = self {
+            sink.append(sound);
+        }
+    }
+
+    fn set_emitter_position(&self, position: [f32; 3]) {
+        if let SpatialSink::Device(sink) = self {
+            sink.set_emitter_position(position);
+        }
+    }
+
+    fn set_left_ear_position(&self, position: [f32; 3]) {
+        if let SpatialSink::Device(sink) = self {
+            sink.set_left_ear_position(position);
+        }
+    }
+
+    fn set_right_ear_position(&self, position: [f32; 3]) {
+        if let SpatialSink::Device(sink) = self {
+            sink.set_right_ear_position(position);
+        }
+    }
+
+    fn pause(&self) {
+        match self {
+            SpatialSink::Device(sink) => sink.pause(),
+            SpatialSink::Silent(sink) => sink.pause(),
+        }
+    }
+
+    fn play(&self) {
+        match self {
+            SpatialSink::Device(sink) => sink.play(),
+            SpatialSink::Silent(sink) => sink.play(),
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        match self {
+            SpatialSink::Device(sink) => sink.is_paused(),
+            SpatialSink::Silent(sink) => sink.is_paused(),
+        }
+    }
+
+    fn empty(&self) -> bool {
+        match self {
+            SpatialSink::Device(sink) => sink.empty(),
+            SpatialSink::Silent(sink) => sink.empty(),
+        }
+    }
+
+    fn volume(&self) -> f32 {
+        match self {
+            SpatialSink::Device(sink) => sink.volume(),
+            SpatialSink::Silent(sink) => sink.volume(),
+        }
+    }
+
+    fn set_volume(&self, value: f32) {
+        match self {
+            SpatialSink::Device(sink) => sink.set_volume(value),
+            SpatialSink::Silent(sink) => sink.set_volume(value),
+        }
+    }
+
+    fn detach(self) {
+        match self {
+            SpatialSink::Device(sink) => sink.detach(),
+            SpatialSink::Silent(sink) => sink.detach(),
+        }
+    }
+}
+
 /// A source of audio data located in space relative to a listener's ears.
 /// Will stop playing when dropped.
+///
+/// In a [silent](struct.AudioContext.html#method.new_optional) context, spatial sources
+/// can be created and used as normal, they just never make a sound and never report as playing.
 pub struct SpatialSource {
-    sink: rodio::SpatialSink,
+    sink: SpatialSink,
     state: SourceState,
     left_ear: mint::Point3<f32>,
     right_ear: mint::Point3<f32>,
     emitter_position: mint::Point3<f32>,
+    ears_override: bool,
 }
 
 impl SpatialSource {
@@ -471,21 +2014,21 @@ impl SpatialSource {
                 "Could not decode the given audio data".to_string(),
             ));
         }
-        let sink = rodio::SpatialSink::try_new(
-            audio.device(),
-            [0.0, 0.0, 0.0],
-            [-1.0, 0.0, 0.0],
-            [1.0, 0.0, 0.0],
+        let sink = audio.new_spatial_sink(
+            [0.0, 0.0, 0.0].into(),
+            [-1.0, 0.0, 0.0].into(),
+            [1.0, 0.0, 0.0].into(),
         )?;
 
         let cursor = io::Cursor::new(data);
 
         Ok(SpatialSource {
             sink,
-            state: SourceState::new(cursor),
+            state: SourceState::new(cursor, audio.shared.clone()),
             left_ear: [-1.0, 0.0, 0.0].into(),
             right_ear: [1.0, 0.0, 0.0].into(),
             emitter_position: [0.0, 0.0, 0.0].into(),
+            ears_override: false,
         })
     }
 }
@@ -493,36 +2036,36 @@ impl SpatialSource {
 impl SoundSource for SpatialSource {
     /// Plays the `SpatialSource`; waits until done if the sound is currently playing.
     fn play_later(&self) -> GameResult {
+        self.apply_global_listener();
+        if self.state.shared.silent {
+            return Ok(());
+        }
+
         // Creating a new Decoder each time seems a little messy,
         // since it may do checking and data-type detection that is
         // redundant, but it's not super expensive.
         // See https://github.com/ggez/ggez/issues/98 for discussion
         use rodio::Source;
-        let cursor = self.state.data.clone();
-
-        let counter = self.state.play_time.clone();
-        let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
-            + self.state.query_interval.subsec_micros() as usize;
 
         if self.state.repeat {
-            let sound = rodio::Decoder::new(cursor)?
-                .repeat_infinite()
+            let sound = DecodeRepeat::new(self.state.data.clone(), self.state.ended_early.clone())?
                 .skip_duration(self.state.skip_duration)
                 .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
+                .fade_in(self.state.fade_in);
+            let sound = self.state.count_samples(sound);
+            self.sink
+                .append(with_shared_state(sound, self.state.shared.clone()));
         } else {
-            let sound = rodio::Decoder::new(cursor)?
+            let sound = self
+                .state
+                .data
+                .decoder()?
                 .skip_duration(self.state.skip_duration)
                 .speed(self.state.speed)
-                .fade_in(self.state.fade_in)
-                .periodic_access(self.state.query_interval, move |_| {
-                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
-                });
-            self.sink.append(sound);
+                .fade_in(self.state.fade_in);
+            let sound = self.state.count_samples(sound);
+            self.sink
+                .append(with_shared_state(sound, self.state.shared.clone()));
         }
 
         Ok(())
@@ -533,13 +2076,8 @@ impl SoundSource for SpatialSource {
         self.stop(audio)?;
         self.play_later()?;
 
-        let device = audio.device();
-        let new_sink = rodio::SpatialSink::try_new(
-            device,
-            self.emitter_position.into(),
-            self.left_ear.into(),
-            self.right_ear.into(),
-        )?;
+        let new_sink =
+            audio.new_spatial_sink(self.emitter_position, self.left_ear, self.right_ear)?;
         let old_sink = mem::replace(&mut self.sink, new_sink);
         old_sink.detach();
 
@@ -590,13 +2128,7 @@ impl SoundSource for SpatialSource {
         // We also need to carry over information from the previous sink.
         let volume = self.volume();
 
-        let device = audio.device();
-        self.sink = rodio::SpatialSink::try_new(
-            device,
-            self.emitter_position.into(),
-            self.left_ear.into(),
-            self.right_ear.into(),
-        )?;
+        self.sink = audio.new_spatial_sink(self.emitter_position, self.left_ear, self.right_ear)?;
         self.state.play_time.store(0, Ordering::SeqCst);
 
         // Restore information from the previous link.
@@ -628,9 +2160,25 @@ impl SoundSource for SpatialSource {
         self.state.elapsed()
     }
 
+    fn duration(&self) -> Option<time::Duration> {
+        self.state.duration()
+    }
+
+    fn take_error(&self) -> Option<GameError> {
+        self.state.take_error()
+    }
+
+    fn progress(&self) -> Option<f32> {
+        self.state.progress()
+    }
+
     fn set_query_interval(&mut self, t: time::Duration) {
         self.state.set_query_interval(t)
     }
+
+    fn set_music_clock(&mut self, attached: bool) {
+        self.state.set_music_clock(attached)
+    }
 }
 
 impl SpatialSource {
@@ -641,18 +2189,62 @@ impl SpatialSource {
     {
         self.emitter_position = pos.into();
         self.sink.set_emitter_position(self.emitter_position.into());
+        self.apply_global_listener();
     }
 
     /// Set locations of the listener's ears
+    ///
+    /// This overrides the [global listener](struct.AudioContext.html#method.set_global_listener)
+    /// for this source, until [`use_global_listener()`](#method.use_global_listener) is called.
     pub fn set_ears<P>(&mut self, left: P, right: P)
     where
         P: Into<mint::Point3<f32>>,
     {
         self.left_ear = left.into();
         self.right_ear = right.into();
+        self.ears_override = true;
         self.sink.set_left_ear_position(self.left_ear.into());
         self.sink.set_right_ear_position(self.right_ear.into());
     }
+
+    /// Makes the source follow the
+    /// [global listener](struct.AudioContext.html#method.set_global_listener) again
+    /// after its ears were set with [`set_ears()`](#method.set_ears).
+    pub fn use_global_listener(&mut self) {
+        self.ears_override = false;
+        self.apply_global_listener();
+    }
+
+    /// Moves the ears to the nearest of the global and added listeners, if there is one
+    /// and this source doesn't have its own ears.
+    fn apply_global_listener(&self) {
+        if self.ears_override {
+            return;
+        }
+        if let Some(listener) = self.state.shared.nearest_listener(self.emitter_position) {
+            let (left, right) = listener.ears();
+            self.sink.set_left_ear_position(left.into());
+            self.sink.set_right_ear_position(right.into());
+        }
+    }
+
+    /// Pans the sound for a 2D game, placing it at horizontal position `x` on a
+    /// screen that is `width` wide: `0.0` is all the way to the left, `width` all the
+    /// way to the right, and positions off screen are clamped to the edges.
+    ///
+    /// This places the ears one unit to the left and right of the origin and moves the
+    /// emitter between them, replacing whatever was set with [`set_position()`](#method.set_position)
+    /// and [`set_ears()`](#method.set_ears). Since the spatial model attenuates sounds by
+    /// distance to each ear, sounds at the edges are somewhat quieter overall than centered ones.
+    pub fn set_2d_position(&mut self, x: f32, width: f32) {
+        let pan = if width > 0.0 {
+            (x / width * 2.0 - 1.0).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        self.set_ears([-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        self.set_position([pan, 0.0, 0.0]);
+    }
 }
 
 impl fmt::Debug for SpatialSource {
@@ -660,3 +2252,68 @@ impl fmt::Debug for SpatialSource {
         write!(f, "<Spatial audio source: {self:p}>")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mono 16-bit WAV file whose header announces `samples` samples,
+    /// but that only contains the first `present` of them.
+    fn wav(samples: u32, present: u32) -> SoundData {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples * 2).to_le_bytes());
+        for _ in 0..present {
+            wav.extend_from_slice(&1000i16.to_le_bytes());
+        }
+        SoundData::from_bytes(&wav)
+    }
+
+    fn repeat(data: SoundData) -> (DecodeRepeat, Arc<AtomicBool>) {
+        let ended = Arc::new(AtomicBool::new(false));
+        let data = SourceData::Memory(io::Cursor::new(data));
+        (DecodeRepeat::new(data, ended.clone()).unwrap(), ended)
+    }
+
+    #[test]
+    fn repeat_decodes_every_pass() {
+        let (sound, ended) = repeat(wav(8, 8));
+        assert_eq!(sound.take(40).filter(|&s| s == 1000).count(), 40);
+        assert!(!ended.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn music_clock_passes_to_next_layer() {
+        let shared = Arc::new(SharedState::default());
+        let layer = |play_time| {
+            let mut state = SourceState::new(io::Cursor::new(wav(8, 8)), shared.clone());
+            state.play_time.store(play_time, Ordering::SeqCst);
+            state.set_music_clock(true);
+            state
+        };
+        let drums = layer(1000);
+        let bass = layer(990);
+        assert_eq!(shared.music_clock(), time::Duration::from_micros(1000));
+        drop(drums);
+        assert_eq!(shared.music_clock(), time::Duration::from_micros(990));
+        drop(bass);
+        assert_eq!(shared.music_clock(), time::Duration::ZERO);
+    }
+
+    #[test]
+    fn repeat_flags_truncated_wav() {
+        let (sound, ended) = repeat(wav(8, 4));
+        assert_eq!(sound.take(40).count(), 40);
+        assert!(ended.load(Ordering::SeqCst));
+    }
+}
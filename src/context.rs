@@ -9,6 +9,7 @@ pub use winit;
 use crate::audio;
 use crate::conf;
 use crate::error::GameResult;
+use crate::event;
 use crate::filesystem::Filesystem;
 use crate::graphics;
 use crate::graphics::GraphicsContext;
@@ -42,7 +43,8 @@ pub struct Context {
     pub gfx: GraphicsContext,
     /// Timer state.
     pub time: timer::TimeContext,
-    /// Audio context.
+    /// Audio context. It is silent if there was no usable output device, see
+    /// [`AudioContext::has_device()`](crate::audio::AudioContext::has_device).
     #[cfg(feature = "audio")]
     pub audio: audio::AudioContext,
     /// Keyboard input context.
@@ -52,6 +54,8 @@ pub struct Context {
     /// Gamepad input context.
     #[cfg(feature = "gamepad")]
     pub gamepad: input::gamepad::GamepadContext,
+    /// This frame's input events, if recording them is enabled.
+    pub input_events: input::EventQueue,
 
     /// The Conf object the Context was created with.
     /// It's here just so that we can see the original settings,
@@ -66,6 +70,37 @@ pub struct Context {
     ///
     /// It's exposed here for people who want to roll their own event loop.
     pub quit_requested: bool,
+    /// The exit code the process ends with once the event loop stops.
+    /// Set with [`Context::request_quit_with_code()`](crate::Context::request_quit_with_code).
+    pub(crate) exit_code: i32,
+    /// The key that quits the game in the default `key_down_event()`, if any.
+    pub(crate) quit_key: Option<input::keyboard::KeyCode>,
+    /// The key that minimizes the window, if any.
+    pub(crate) boss_key: Option<input::keyboard::KeyCode>,
+    /// Observes every error returned by an event callback.
+    pub(crate) error_hook: Option<ErrorHook>,
+    /// How the event loop waits for new events.
+    pub(crate) loop_mode: event::LoopMode,
+    /// Whether or not the event loop draws a frame after each update.
+    pub(crate) draw_enabled: bool,
+    /// Whether or not the window was resized since the last frame was drawn.
+    /// The next frame is then drawn on `RedrawRequested`, in step with the resize.
+    pub(crate) resize_redraw: bool,
+    /// Whether or not the first frame has been started yet.
+    /// Used to fire [`first_frame_event`](crate::event::EventHandler::first_frame_event) only once.
+    pub(crate) first_frame_started: bool,
+    /// Whether or not [`event::pump()`](crate::event::pump) has run before.
+    /// `winit` repeats its startup events on every pump, which are only handled the first time.
+    pub(crate) pumped: bool,
+    /// The smallest logical size the window may be resized to, if any.
+    /// See [`Context::set_min_logical_size()`](crate::Context::set_min_logical_size).
+    pub(crate) min_logical_size: Option<winit::dpi::LogicalSize<f32>>,
+    /// Whether or not the event currently being dispatched has been handled.
+    pub(crate) event_outcome: event::EventOutcome,
+    /// When the latest event was processed, see [`Context::event_timestamp()`].
+    pub(crate) event_timestamp: std::time::Instant,
+    /// Whether or not the window has keyboard focus, see [`Context::has_focus()`].
+    pub(crate) focused: bool,
 }
 
 impl Context {
@@ -76,6 +111,170 @@ impl Context {
     pub fn request_quit(&mut self) {
         self.quit_requested = true;
     }
+
+    /// Like [`request_quit()`](#method.request_quit), but makes the process exit with the given
+    /// status code once the loop breaks, so tools built on ggez can report success or failure
+    /// to a parent process. If [`quit_event`](crate::event::EventHandler::quit_event) cancels
+    /// the quit, the exit code is reset to `0`.
+    pub fn request_quit_with_code(&mut self, code: i32) {
+        self.exit_code = code;
+        self.quit_requested = true;
+    }
+
+    /// Returns the status code the process will exit with when the event loop ends.
+    ///
+    /// After a fatal error this is `1`, unless another non-zero code was set.
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    /// Sets the key that quits the game, or `None` to not quit on any key.
+    ///
+    /// This is only honored by the default implementation of
+    /// [`key_down_event`](crate::event::EventHandler::key_down_event). Defaults to `Escape`.
+    pub fn set_quit_key(&mut self, key: Option<input::keyboard::KeyCode>) {
+        self.quit_key = key;
+    }
+
+    /// Returns the key that quits the game, if any.
+    pub fn quit_key(&self) -> Option<input::keyboard::KeyCode> {
+        self.quit_key
+    }
+
+    /// Sets a "boss key" that instantly minimizes the window when pressed, or `None` to disable it.
+    ///
+    /// Both the press and the release of the key are swallowed by the event loop, so neither
+    /// [`key_down_event`](crate::event::EventHandler::key_down_event) nor
+    /// [`key_up_event`](crate::event::EventHandler::key_up_event) is called for it, and it
+    /// never shows up as pressed in the [`KeyboardContext`](crate::input::keyboard::KeyboardContext).
+    /// Off by default.
+    pub fn set_boss_key(&mut self, key: Option<input::keyboard::KeyCode>) {
+        self.boss_key = key;
+    }
+
+    /// Returns the key that minimizes the window, if any.
+    pub fn boss_key(&self) -> Option<input::keyboard::KeyCode> {
+        self.boss_key
+    }
+
+    /// Sets how the [`event::run()`](crate::event::run) loop waits for new events.
+    ///
+    /// The default, [`LoopMode::Poll`](crate::event::LoopMode::Poll), runs frames back to back.
+    /// A menu can switch to [`LoopMode::Wait`](crate::event::LoopMode::Wait) to only run a frame
+    /// when there is input or another window event, and switch back for gameplay.
+    /// Scheduled events from [`TimeContext::after()`](crate::timer::TimeContext::after)
+    /// don't wake the loop on their own, so use `WaitFor` if you rely on them.
+    pub fn set_loop_mode(&mut self, mode: event::LoopMode) {
+        self.loop_mode = mode;
+    }
+
+    /// Returns how the event loop waits for new events.
+    pub fn loop_mode(&self) -> event::LoopMode {
+        self.loop_mode
+    }
+
+    /// Sets whether or not the [`event::run()`](crate::event::run) loop draws a frame after
+    /// each [`update()`](crate::event::EventHandler::update).
+    ///
+    /// While disabled, frames only run the update logic: no surface is acquired and
+    /// [`draw()`](crate::event::EventHandler::draw) and
+    /// [`post_draw()`](crate::event::EventHandler::post_draw) aren't called, so the window keeps
+    /// showing the last drawn frame. Useful to skip rendering while nothing on screen changes.
+    /// Enabled by default.
+    pub fn set_draw_enabled(&mut self, enabled: bool) {
+        self.draw_enabled = enabled;
+    }
+
+    /// Returns whether or not the event loop draws a frame after each update.
+    pub fn draw_enabled(&self) -> bool {
+        self.draw_enabled
+    }
+
+    /// Returns whether or not the window currently has keyboard focus.
+    ///
+    /// This is kept up to date by [`event::process_event()`](crate::event::process_event),
+    /// from the same events that are passed to
+    /// [`focus_event()`](crate::event::EventHandler::focus_event) and that pause audio
+    /// when [`AudioContext::set_pause_on_focus_loss()`](crate::audio::AudioContext::set_pause_on_focus_loss) is enabled.
+    pub fn has_focus(&self) -> bool {
+        self.focused
+    }
+
+    /// Sets a function that is called with every error returned by an
+    /// [`EventHandler`](crate::event::EventHandler) callback, for logging or reporting
+    /// them in one place.
+    ///
+    /// It is called by [`event::run()`](crate::event::run) right before
+    /// [`on_error()`](crate::event::EventHandler::on_error), which still decides on its
+    /// own whether the error is fatal. Replaces any previously set hook.
+    pub fn set_error_hook(&mut self, hook: impl Fn(event::ErrorOrigin, &dyn fmt::Debug) + 'static) {
+        self.error_hook = Some(Box::new(hook));
+    }
+
+    /// Removes the function set with [`set_error_hook()`](#method.set_error_hook).
+    pub fn clear_error_hook(&mut self) {
+        self.error_hook = None;
+    }
+
+    /// Returns a summary of the window, graphics adapter, audio device, connected gamepads
+    /// and frame rate, meant to be pasted into bug reports.
+    ///
+    /// The exact format is not stable and may change between versions.
+    pub fn debug_dump(&self) -> String {
+        let mut out = format!("ggez {}\n", env!("CARGO_PKG_VERSION"));
+        // writing to a `String` can't fail
+        let _ = self.write_debug_dump(&mut out);
+        out
+    }
+
+    fn write_debug_dump(&self, out: &mut String) -> fmt::Result {
+        self.gfx.write_debug_dump(out)?;
+        self.time.write_debug_dump(out)?;
+        #[cfg(feature = "audio")]
+        self.audio.write_debug_dump(out)?;
+        #[cfg(feature = "gamepad")]
+        self.gamepad.write_debug_dump(out)?;
+        Ok(())
+    }
+
+    /// Sets the smallest logical size the window may be resized to, or `None` to remove the limit.
+    ///
+    /// Whenever the window gets resized below this size it is requested to grow back,
+    /// and [`resize_event`](crate::event::EventHandler::resize_event) is called with the
+    /// clamped size instead, so game code never has to deal with a window that is too small.
+    pub fn set_min_logical_size(&mut self, size: Option<winit::dpi::LogicalSize<f32>>) {
+        self.min_logical_size = size;
+    }
+
+    /// Returns the smallest logical size the window may be resized to, if any.
+    pub fn min_logical_size(&self) -> Option<winit::dpi::LogicalSize<f32>> {
+        self.min_logical_size
+    }
+
+    /// Reports whether or not the event currently being dispatched has been handled.
+    /// See [`EventOutcome`](crate::event::EventOutcome).
+    ///
+    /// This is reset to [`EventOutcome::Ignored`](crate::event::EventOutcome::Ignored)
+    /// before every event.
+    pub fn set_event_outcome(&mut self, outcome: event::EventOutcome) {
+        self.event_outcome = outcome;
+    }
+
+    /// Returns whether or not the event currently being dispatched has been handled.
+    pub fn event_outcome(&self) -> event::EventOutcome {
+        self.event_outcome
+    }
+
+    /// Returns when the latest event was processed by
+    /// [`event::process_event()`](crate::event::process_event).
+    ///
+    /// Inside of an event callback this is the time of the event being dispatched,
+    /// which allows ordering inputs at a finer granularity than once per frame,
+    /// e.g. for input buffering. Keyboard events carry it in
+    /// [`KeyInput::timestamp`](crate::input::keyboard::KeyInput::timestamp) as well.
+    pub fn event_timestamp(&self) -> std::time::Instant {
+        self.event_timestamp
+    }
 }
 
 // This is ugly and hacky but greatly improves ergonomics.
@@ -158,6 +357,8 @@ impl HasMut<GraphicsContext> for Context {
     }
 }
 
+pub(crate) type ErrorHook = Box<dyn Fn(event::ErrorOrigin, &dyn fmt::Debug)>;
+
 impl fmt::Debug for Context {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "<Context: {self:p}>")
@@ -167,17 +368,23 @@ impl fmt::Debug for Context {
 impl Context {
     /// Tries to create a new Context using settings from the given [`Conf`](../conf/struct.Conf.html) object.
     /// Usually called by [`ContextBuilder::build()`](struct.ContextBuilder.html#method.build).
-    fn from_conf(
+    fn from_conf<U: 'static>(
         game_id: &str,
         conf: conf::Conf,
         fs: Filesystem,
-    ) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
+        #[cfg(feature = "audio")] require_audio_device: bool,
+    ) -> GameResult<(Context, winit::event_loop::EventLoop<U>)> {
         #[cfg(feature = "audio")]
-        let audio_context = audio::AudioContext::new(&fs)?;
-        let events_loop = winit::event_loop::EventLoop::new();
+        let audio_context = if require_audio_device {
+            audio::AudioContext::new(&fs)?
+        } else {
+            audio::AudioContext::new_optional(&fs).0
+        };
+        let events_loop = winit::event_loop::EventLoopBuilder::with_user_event().build();
         let timer_context = timer::TimeContext::new();
         let graphics_context =
             graphics::context::GraphicsContext::new(game_id, &events_loop, &conf, &fs)?;
+        let focused = graphics_context.window.has_focus();
 
         let ctx = Context {
             conf,
@@ -185,11 +392,25 @@ impl Context {
             gfx: graphics_context,
             continuing: true,
             quit_requested: false,
+            exit_code: 0,
+            quit_key: Some(input::keyboard::KeyCode::Escape),
+            boss_key: None,
+            error_hook: None,
+            loop_mode: event::LoopMode::Poll,
+            draw_enabled: true,
+            resize_redraw: false,
+            first_frame_started: false,
+            pumped: false,
+            min_logical_size: None,
+            event_outcome: event::EventOutcome::Ignored,
+            event_timestamp: std::time::Instant::now(),
+            focused,
             time: timer_context,
             #[cfg(feature = "audio")]
             audio: audio_context,
             keyboard: input::keyboard::KeyboardContext::new(),
             mouse: input::mouse::MouseContext::new(),
+            input_events: input::EventQueue::new(),
             #[cfg(feature = "gamepad")]
             gamepad: input::gamepad::GamepadContext::new()?,
         };
@@ -212,6 +433,9 @@ pub struct ContextBuilder {
     pub(crate) paths: Vec<path::PathBuf>,
     pub(crate) memory_zip_files: Vec<Cow<'static, [u8]>>,
     pub(crate) load_conf_file: bool,
+    pub(crate) surface_format: Option<graphics::ImageFormat>,
+    #[cfg(feature = "audio")]
+    pub(crate) require_audio_device: bool,
 }
 
 impl ContextBuilder {
@@ -226,6 +450,9 @@ impl ContextBuilder {
             paths: vec![],
             memory_zip_files: vec![],
             load_conf_file: true,
+            surface_format: None,
+            #[cfg(feature = "audio")]
+            require_audio_device: false,
         }
     }
 
@@ -244,6 +471,10 @@ impl ContextBuilder {
     }
 
     /// Sets the graphics backend.
+    ///
+    /// Use this to force e.g. Vulkan or OpenGL when a driver has bugs with the other one.
+    /// If no adapter is available for the chosen backend, ggez logs a warning and falls
+    /// back to [`Backend::All`](crate::conf::Backend::All).
     #[must_use]
     pub fn backend(mut self, backend: conf::Backend) -> Self {
         self.conf.backend = backend;
@@ -319,8 +550,44 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets the image format of the window surface, instead of the one the surface prefers.
+    ///
+    /// This lets you opt into HDR or wide-gamut output, e.g. with `Rgba16Float`. Building
+    /// the context fails if the surface doesn't support the format; see
+    /// [`GraphicsContext::supported_formats()`](crate::graphics::GraphicsContext::supported_formats).
+    #[must_use]
+    pub fn surface_format(mut self, format: graphics::ImageFormat) -> Self {
+        self.surface_format = Some(format);
+        self
+    }
+
+    /// Sets whether or not building the context fails if there is no usable audio
+    /// output device.
+    ///
+    /// Defaults to `false`: without a device, a warning is logged and the context
+    /// gets a silent [`AudioContext`](crate::audio::AudioContext), which
+    /// [`has_device()`](crate::audio::AudioContext::has_device) tells apart.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn require_audio_device(mut self, require: bool) -> Self {
+        self.require_audio_device = require;
+        self
+    }
+
     /// Build the `Context`.
     pub fn build(self) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
+        self.build_with_user_event()
+    }
+
+    /// Build the `Context`, with an event loop that carries custom events of type `U`.
+    ///
+    /// Events can be sent from other threads through an
+    /// [`EventLoopProxy`](winit::event_loop::EventLoopProxy) created with
+    /// [`EventLoop::create_proxy()`](winit::event_loop::EventLoop::create_proxy),
+    /// and are passed to [`EventHandler::user_event()`](crate::event::EventHandler::user_event).
+    pub fn build_with_user_event<U: 'static>(
+        self,
+    ) -> GameResult<(Context, winit::event_loop::EventLoop<U>)> {
         let fs = Filesystem::new(
             self.game_id.as_ref(),
             self.author.as_ref(),
@@ -342,7 +609,17 @@ impl ContextBuilder {
             self.conf
         };
 
-        Context::from_conf(self.game_id.as_ref(), config, fs)
+        let (mut ctx, events_loop) = Context::from_conf(
+            self.game_id.as_ref(),
+            config,
+            fs,
+            #[cfg(feature = "audio")]
+            self.require_audio_device,
+        )?;
+        if let Some(format) = self.surface_format {
+            ctx.gfx.set_surface_format(format)?;
+        }
+        Ok((ctx, events_loop))
     }
 }
 
@@ -30,6 +30,14 @@ pub enum GameError {
     WindowError(String),
     /// Something went wrong trying to create a window
     WindowCreationError(Arc<winit::error::OsError>),
+    /// Something went wrong trying to create the graphics surface for the window,
+    /// e.g. because there is no usable GPU or display.
+    SurfaceCreationError(wgpu::CreateSurfaceError),
+    /// The next frame of the window surface couldn't be acquired.
+    ///
+    /// `Lost` and `Outdated` are already retried once after reconfiguring the surface,
+    /// and `Timeout` only means the frame is skipped, so usually only `OutOfMemory` is fatal.
+    SurfaceError(wgpu::SurfaceError),
     /// Something went wrong trying to read from a file
     #[allow(clippy::upper_case_acronyms)]
     IOError(Arc<std::io::Error>),
@@ -72,6 +80,11 @@ impl fmt::Display for GameError {
             GameError::RequestDeviceError(ref e) => {
                 write!(f, "Failed to request logical device: {e}")
             }
+            GameError::WindowCreationError(ref e) => write!(f, "Failed to create window: {e}"),
+            GameError::SurfaceCreationError(ref e) => {
+                write!(f, "Failed to create window surface: {e}")
+            }
+            GameError::SurfaceError(ref e) => write!(f, "Failed to acquire next frame: {e}"),
             GameError::GlyphBrushError(ref e) => write!(f, "Text rendering error: {e}"),
             GameError::FontSelectError(ref e) => write!(f, "No such font '{e}'"),
             GameError::BufferAsyncError(ref e) => write!(f, "Async buffer map error: {e}"),
@@ -85,6 +98,8 @@ impl Error for GameError {
         match *self {
             GameError::RequestDeviceError(ref e) => Some(e),
             GameError::WindowCreationError(ref e) => Some(&**e),
+            GameError::SurfaceCreationError(ref e) => Some(e),
+            GameError::SurfaceError(ref e) => Some(e),
             GameError::IOError(ref e) => Some(&**e),
             GameError::FontError(ref e) => Some(e),
             GameError::GlyphBrushError(ref e) => Some(e),
@@ -183,6 +198,18 @@ impl From<wgpu::RequestDeviceError> for GameError {
     }
 }
 
+impl From<wgpu::CreateSurfaceError> for GameError {
+    fn from(s: wgpu::CreateSurfaceError) -> GameError {
+        GameError::SurfaceCreationError(s)
+    }
+}
+
+impl From<wgpu::SurfaceError> for GameError {
+    fn from(s: wgpu::SurfaceError) -> GameError {
+        GameError::SurfaceError(s)
+    }
+}
+
 impl From<Arc<winit::error::OsError>> for GameError {
     fn from(s: Arc<winit::error::OsError>) -> GameError {
         GameError::WindowCreationError(s)
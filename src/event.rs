@@ -14,6 +14,8 @@ use winit::{self, dpi};
 
 /// A mouse button.
 pub use winit::event::{MouseButton, ScanCode};
+/// The light or dark theme of a window.
+pub use winit::window::Theme;
 
 /// An analog axis of some device (gamepad thumbstick, joystick...).
 #[cfg(feature = "gamepad")]
@@ -32,16 +34,24 @@ pub mod winit_event {
 #[cfg(feature = "gamepad")]
 pub use crate::input::gamepad::GamepadId;
 use crate::input::keyboard::{KeyCode, KeyInput, KeyMods};
-use crate::GameError;
+use crate::input::InputEvent;
+use crate::{GameError, GameResult};
 
 use self::winit_event::{
-    ElementState, Event, KeyboardInput, MouseScrollDelta, TouchPhase, WindowEvent,
+    DeviceEvent, ElementState, Event, KeyboardInput, MouseScrollDelta, TouchPhase, WindowEvent,
 };
 /// `winit` event loop.
 pub use winit::event_loop::{ControlFlow, EventLoop};
 
 use crate::context::Context;
 
+mod driver;
+mod stack;
+mod threaded;
+pub use self::driver::TestDriver;
+pub use self::stack::HandlerStack;
+pub use self::threaded::{Simulation, ThreadedUpdate};
+
 /// Used in [`EventHandler::on_error()`](trait.EventHandler.html#method.on_error)
 /// to specify where an error originated
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -56,6 +66,8 @@ pub enum ErrorOrigin {
     MouseButtonUpEvent,
     /// error originated in `mouse_motion_event()`
     MouseMotionEvent,
+    /// error originated in `raw_mouse_motion_event()`
+    RawMouseMotionEvent,
     /// error originated in `mouse_enter_or_leave()`
     MouseEnterOrLeave,
     /// error originated in `mouse_wheel_event()`
@@ -80,6 +92,99 @@ pub enum ErrorOrigin {
     QuitEvent,
     /// error originated in `resize_event()`
     ResizeEvent,
+    /// error originated in `first_frame_event()`
+    FirstFrameEvent,
+    /// error originated in `scheduled_event()`
+    ScheduledEvent,
+    /// error originated in `on_suspend()`
+    SuspendEvent,
+    /// error originated in `on_resume()`
+    ResumeEvent,
+    /// error originated in `user_event()`
+    UserEvent,
+    /// error originated in `pre_update()`
+    PreUpdate,
+    /// error originated in `post_draw()`
+    PostDraw,
+    /// error originated in `window_hit_test()`
+    WindowHitTest,
+    /// error originated in `theme_changed_event()`
+    ThemeChangedEvent,
+    /// error originated in `filter_event()`
+    FilterEvent,
+}
+
+/// Why [`EventHandler::quit_event()`](trait.EventHandler.html#method.quit_event) was called.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum QuitReason {
+    /// The user clicked the window's close button, or the OS asked it to close otherwise.
+    WindowClose,
+    /// The game asked to quit with [`Context::request_quit()`](crate::Context::request_quit)
+    /// or [`Context::request_quit_with_code()`](crate::Context::request_quit_with_code),
+    /// which includes the default handling of the quit key.
+    Requested,
+}
+
+/// How the event loop waits for new events, see
+/// [`Context::set_loop_mode()`](crate::Context::set_loop_mode).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum LoopMode {
+    /// Run the next frame as soon as the current one is done, whether or not
+    /// there are new events. This is what games usually want.
+    #[default]
+    Poll,
+    /// Sleep until a new event arrives, e.g. input or a window resize, then run a frame.
+    /// Saves CPU and battery on static screens like menus.
+    ///
+    /// In this mode, drawing happens when the window system asks for a redraw, which
+    /// ggez requests after every update. Use
+    /// [`GraphicsContext::request_redraw()`](crate::graphics::GraphicsContext::request_redraw)
+    /// to wake the loop up for a new frame without any input.
+    Wait,
+    /// Like `Wait`, but also wake up to run a frame once the given time has passed
+    /// without any events, e.g. to keep a blinking cursor going.
+    WaitFor(std::time::Duration),
+}
+
+impl LoopMode {
+    fn control_flow(self) -> ControlFlow {
+        match self {
+            LoopMode::Poll => ControlFlow::Poll,
+            LoopMode::Wait => ControlFlow::Wait,
+            LoopMode::WaitFor(timeout) => match std::time::Instant::now().checked_add(timeout) {
+                Some(deadline) => ControlFlow::WaitUntil(deadline),
+                None => ControlFlow::Wait,
+            },
+        }
+    }
+}
+
+/// What part of the window a point belongs to, as returned by
+/// [`EventHandler::window_hit_test()`](trait.EventHandler.html#method.window_hit_test).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum HitRegion {
+    /// The game's own content; clicks are passed on to the event handler.
+    #[default]
+    Client,
+    /// A title bar; dragging it with the left mouse button moves the window.
+    Caption,
+    /// A window border; dragging it with the left mouse button resizes the window in the given direction.
+    Resize(winit::window::ResizeDirection),
+}
+
+/// Whether or not an event callback has handled the event it was given.
+///
+/// Callbacks can report this with [`Context::set_event_outcome()`](crate::Context::set_event_outcome),
+/// which lets handlers that wrap several other handlers, such as a UI layered on
+/// top of the game world, stop passing an event on once it was dealt with.
+/// The default for every event is `Ignored`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum EventOutcome {
+    /// The event was handled and should not be passed on.
+    Handled,
+    /// The event was not handled and may be passed on.
+    #[default]
+    Ignored,
 }
 
 /// A trait defining event callbacks.  This is your primary interface with
@@ -95,7 +200,12 @@ pub enum ErrorOrigin {
 ///
 /// For the error type simply choose the default [`GameError`](../error/enum.GameError.html),
 /// or something more generic, if your situation requires it.
-pub trait EventHandler<E = GameError>
+///
+/// `U` is the type of custom events sent to the event loop through an
+/// [`EventLoopProxy`](https://docs.rs/winit/0.28/winit/event_loop/struct.EventLoopProxy.html),
+/// see [`ContextBuilder::build_with_user_event()`](../struct.ContextBuilder.html#method.build_with_user_event).
+/// Use an enum if you need to send different kinds of events.
+pub trait EventHandler<E = GameError, U = ()>
 where
     E: std::fmt::Debug,
 {
@@ -103,12 +213,63 @@ where
     /// This should be where the game's logic takes place.
     fn update(&mut self, _ctx: &mut Context) -> Result<(), E>;
 
+    /// Called every frame right before [`update()`](#tymethod.update).
+    ///
+    /// Together with [`post_draw()`](#method.post_draw) this brackets a frame, for
+    /// cross-cutting concerns like profiling that shouldn't clutter the game's own
+    /// `update()` and `draw()`.
+    fn pre_update(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called exactly once, right after the first frame has begun and before the
+    /// first call to [`draw()`](#tymethod.draw).
+    ///
+    /// At this point the window and its surface have their final size, which makes
+    /// this a good place for GPU-dependent setup, such as creating render targets
+    /// matching the size of the framebuffer.
+    fn first_frame_event(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Called to do the drawing of your game.
     /// You probably want to start this with
     /// [`Canvas::from_frame`](../graphics/struct.Canvas.html#method.from_frame) and end it
     /// with [`Canvas::finish`](../graphics/struct.Canvas.html#method.finish).
     fn draw(&mut self, _ctx: &mut Context) -> Result<(), E>;
 
+    /// Called every frame right after [`draw()`](#tymethod.draw), while the frame is
+    /// still in progress, so it can draw on top of it, e.g. a debug overlay.
+    fn post_draw(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called with every window and device event before ggez handles it; returning
+    /// `false` drops the event, so neither ggez nor any other callback sees it.
+    ///
+    /// This is the one place to swallow or log events globally, e.g. to ignore all
+    /// input during a cutscene. Keys and buttons released while their events are being
+    /// dropped stay pressed as far as ggez knows, and dropping window management events
+    /// like `Resized` keeps ggez from reacting to them, so it's usually best to only drop
+    /// input events. Passes everything through by default.
+    fn filter_event(&mut self, _ctx: &mut Context, _event: &Event<U>) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    /// Called when the left mouse button is pressed, to find out which part of the
+    /// window the cursor is over; `x` and `y` are the cursor position in physical pixels.
+    ///
+    /// Games with a borderless window (see
+    /// [`WindowSetup`](../conf/struct.WindowSetup.html) and
+    /// [`WindowMode::borderless`](../conf/struct.WindowMode.html#structfield.borderless))
+    /// can draw their own window frame and return [`HitRegion::Caption`] or
+    /// [`HitRegion::Resize`] for it, which lets the user move and resize the window as usual.
+    /// In that case `mouse_button_down_event()` isn't called for the click.
+    /// Returns [`HitRegion::Client`] by default.
+    fn window_hit_test(&mut self, _ctx: &mut Context, _x: f32, _y: f32) -> Result<HitRegion, E> {
+        Ok(HitRegion::Client)
+    }
+
     /// A mouse button was pressed
     fn mouse_button_down_event(
         &mut self,
@@ -144,6 +305,14 @@ where
         Ok(())
     }
 
+    /// The mouse device reported raw motion, independent of the cursor position.
+    /// This keeps firing while the cursor is grabbed, which makes it the right
+    /// choice for camera controls. The values are scaled according to
+    /// [`MouseContext::set_raw_sensitivity()`](../input/mouse/struct.MouseContext.html#method.set_raw_sensitivity).
+    fn raw_mouse_motion_event(&mut self, _ctx: &mut Context, _dx: f64, _dy: f64) -> Result<(), E> {
+        Ok(())
+    }
+
     /// mouse entered or left window area
     fn mouse_enter_or_leave(&mut self, _ctx: &mut Context, _entered: bool) -> Result<(), E> {
         Ok(())
@@ -158,7 +327,9 @@ where
     /// A keyboard button was pressed.
     ///
     /// The default implementation of this will call [`ctx.request_quit()`](crate::Context::request_quit)
-    /// when the escape key is pressed. If you override this with your own
+    /// when the escape key, or the key set with [`ctx.set_quit_key()`](crate::Context::set_quit_key),
+    /// is pressed, unless text entry has [captured](crate::input::keyboard::KeyboardContext::push_input_capture)
+    /// the keyboard. If you override this with your own
     /// event handler you have to re-implement that functionality yourself.
     fn key_down_event(
         &mut self,
@@ -166,7 +337,10 @@ where
         input: KeyInput,
         _repeated: bool,
     ) -> Result<(), E> {
-        if input.keycode == Some(KeyCode::Escape) {
+        if input.keycode.is_some()
+            && input.keycode == ctx.quit_key()
+            && !ctx.keyboard.is_input_captured()
+        {
             ctx.request_quit();
         }
         Ok(())
@@ -214,6 +388,8 @@ where
     }
 
     /// A gamepad button was pressed; `id` identifies which gamepad.
+    /// Use [`GamepadContext::slot_of()`](../input/gamepad/struct.GamepadContext.html#method.slot_of)
+    /// to find out which player it belongs to.
     #[cfg(feature = "gamepad")]
     fn gamepad_button_down_event(
         &mut self,
@@ -247,26 +423,73 @@ where
         Ok(())
     }
 
+    /// A delay set up with [`TimeContext::after()`](../timer/struct.TimeContext.html#method.after)
+    /// has elapsed; `id` is the one that was passed to it.
+    fn scheduled_event(&mut self, _ctx: &mut Context, _id: u64) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Called when the window is shown or hidden.
+    ///
+    /// Before the window loses focus, `key_up_event()` and `mouse_button_up_event()`
+    /// are called for every key and mouse button that is still held down.
     fn focus_event(&mut self, _ctx: &mut Context, _gained: bool) -> Result<(), E> {
         Ok(())
     }
 
+    /// Called when the system switches between a light and dark theme.
+    /// The current theme can be queried with
+    /// [`GraphicsContext::current_theme()`](../graphics/struct.GraphicsContext.html#method.current_theme).
+    ///
+    /// Only reported on Windows, macOS and the web by `winit`.
+    fn theme_changed_event(&mut self, _ctx: &mut Context, _theme: Theme) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when the application is suspended by the OS.
+    ///
+    /// On mobile platforms this is the last chance to release graphics and audio
+    /// resources before the surface goes away; on desktop it's a good place to auto-save.
+    fn on_suspend(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when the application is resumed by the OS.
+    ///
+    /// Note that `winit` also sends a resume event once at startup on all platforms.
+    fn on_resume(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A custom event was sent to the event loop, for example from a network or IO thread.
+    fn user_event(&mut self, _ctx: &mut Context, _event: U) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Called upon a quit event.  If it returns true,
     /// the game does not exit (the quit event is cancelled).
-    fn quit_event(&mut self, _ctx: &mut Context) -> Result<bool, E> {
+    ///
+    /// The `reason` tells a click on the window's close button apart from a quit the
+    /// game requested itself, e.g. to show a different confirmation dialog for each.
+    fn quit_event(&mut self, _ctx: &mut Context, _reason: QuitReason) -> Result<bool, E> {
         debug!("quit_event() callback called, quitting...");
         Ok(false)
     }
 
     /// Called when the user resizes the window, or when it is resized
     /// via [`GraphicsContext::set_mode()`](../graphics/struct.GraphicsContext.html#method.set_mode).
+    /// It is also called once when the event loop starts, before the first
+    /// [`update()`](#tymethod.update), with the initial size of the window.
+    ///
+    /// The new size is the size of the drawable area in physical pixels, the same as
+    /// [`GraphicsContext::drawable_size()`](../graphics/struct.GraphicsContext.html#method.drawable_size).
     fn resize_event(&mut self, _ctx: &mut Context, _width: f32, _height: f32) -> Result<(), E> {
         Ok(())
     }
 
     /// Something went wrong, causing a `GameError` (or some other kind of error, depending on what you specified).
-    /// If this returns true, the error was fatal, so the event loop ends, aborting the game.
+    /// If this returns true, the error was fatal, so the event loop ends, aborting the game
+    /// with a non-zero exit code.
     fn on_error(&mut self, _ctx: &mut Context, _origin: ErrorOrigin, _e: E) -> bool {
         true
     }
@@ -277,307 +500,707 @@ where
 ///
 /// It does not try to do any type of framerate limiting.  See the
 /// documentation for the [`timer`](../timer/index.html) module for more info.
-#[allow(clippy::needless_return)] // necessary as the returns used here are actually necessary to break early from the event loop
-pub fn run<S: 'static, E>(mut ctx: Context, event_loop: EventLoop<()>, mut state: S) -> !
+///
+/// This never returns: when the loop ends the process exits with the code set by
+/// [`Context::request_quit_with_code()`](crate::Context::request_quit_with_code), or `0`.
+/// If the loop ends because of a fatal error, the code is `1` unless another non-zero
+/// code was set.
+pub fn run<S: 'static, E, U: 'static>(mut ctx: Context, event_loop: EventLoop<U>, mut state: S) -> !
 where
-    S: EventHandler<E>,
+    S: EventHandler<E, U>,
     E: std::fmt::Debug,
 {
-    event_loop.run(move |mut event, _, control_flow| {
-        let ctx = &mut ctx;
-        let state = &mut state;
-
-        if ctx.quit_requested {
-            let res = state.quit_event(ctx);
-            ctx.quit_requested = false;
-            if let Ok(false) = res {
-                ctx.continuing = false;
-            } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
-                return;
+    event_loop
+        .run(move |event, _, control_flow| handle_event(&mut ctx, &mut state, event, control_flow))
+}
+
+/// Whether or not the game goes on after [`pump()`](fn.pump.html) returns.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PumpStatus {
+    /// The game is still running; call `pump()` again to run the next frame.
+    Continue,
+    /// The game quit, with the given exit code, see
+    /// [`Context::request_quit_with_code()`](crate::Context::request_quit_with_code).
+    /// A fatal error quits with a non-zero code, like in [`run()`](fn.run.html).
+    Exit(i32),
+}
+
+/// Runs a single iteration of the main loop and returns, for driving the game from
+/// a loop you control instead of handing the thread over to [`run()`](fn.run.html),
+/// e.g. when embedding ggez in another application.
+///
+/// All pending events are handled, then one frame is updated and drawn. If there are
+/// no pending events, this first waits up to `timeout` for one to arrive, or as long as
+/// the [`LoopMode`](enum.LoopMode.html) says if `timeout` is `None`.
+///
+/// This uses `winit`'s `run_return()`, so it shares its caveats: on Windows and macOS it
+/// doesn't return while the window is being resized, and it isn't available on iOS
+/// and the web. `winit` also sends its startup events again on every `run_return()`;
+/// `pump()` only handles them on the first call, so
+/// [`on_resume()`](trait.EventHandler.html#method.on_resume) runs once at startup like
+/// with `run()`, and they don't count as events to stop waiting for.
+#[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+pub fn pump<S, E, U>(
+    ctx: &mut Context,
+    event_loop: &mut EventLoop<U>,
+    state: &mut S,
+    timeout: Option<std::time::Duration>,
+) -> PumpStatus
+where
+    S: EventHandler<E, U> + 'static,
+    E: std::fmt::Debug,
+{
+    use winit::platform::run_return::EventLoopExtRunReturn;
+
+    let mut status = PumpStatus::Continue;
+    let mut saw_events = timeout == Some(std::time::Duration::ZERO);
+    let mut frame_done = false;
+    let first_pump = !ctx.pumped;
+    ctx.pumped = true;
+    let code = event_loop.run_return(|event, _, control_flow| {
+        match event {
+            // repeated by `run_return()` every time
+            Event::NewEvents(winit::event::StartCause::Init) | Event::Resumed if !first_pump => {
+                return
             }
+            Event::NewEvents(_)
+            | Event::Resumed
+            | Event::RedrawEventsCleared
+            | Event::LoopDestroyed => (),
+            // wait for events before running the frame if there aren't any yet
+            Event::MainEventsCleared | Event::RedrawRequested(_) if !saw_events => return,
+            Event::MainEventsCleared => frame_done = true,
+            _ => saw_events = true,
+        }
+        let is_end_of_iteration = matches!(event, Event::RedrawEventsCleared);
+        handle_event(ctx, state, event, control_flow);
+
+        if let ControlFlow::ExitWithCode(code) = *control_flow {
+            status = PumpStatus::Exit(code);
+        } else if is_end_of_iteration {
+            if frame_done {
+                *control_flow = ControlFlow::Exit;
+            } else {
+                // nothing was pending, so wait for something to happen
+                saw_events = true;
+                if let Some(deadline) =
+                    timeout.and_then(|timeout| std::time::Instant::now().checked_add(timeout))
+                {
+                    *control_flow = ControlFlow::WaitUntil(deadline);
+                }
+            }
+        }
+    });
+    if code != 0 {
+        // lost the connection to the display server
+        return PumpStatus::Exit(code);
+    }
+    status
+}
+
+/// Handles a single event of the main loop, see [`run()`](fn.run.html).
+#[allow(clippy::needless_return)] // necessary as the returns used here are actually necessary to break early from the event loop
+fn handle_event<S, E, U>(
+    ctx: &mut Context,
+    state: &mut S,
+    mut event: Event<U>,
+    control_flow: &mut ControlFlow,
+) where
+    S: EventHandler<E, U> + 'static,
+    E: std::fmt::Debug,
+{
+    if ctx.quit_requested {
+        let res = state.quit_event(ctx, QuitReason::Requested);
+        ctx.quit_requested = false;
+        if let Ok(false) = res {
+            ctx.continuing = false;
+        } else if let Ok(true) = res {
+            ctx.exit_code = 0;
+        } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
+            return;
         }
-        if !ctx.continuing {
-            *control_flow = ControlFlow::Exit;
+    }
+    if !ctx.continuing {
+        *control_flow = ControlFlow::ExitWithCode(ctx.exit_code);
+        return;
+    }
+
+    *control_flow = ctx.loop_mode.control_flow();
+
+    if let Event::WindowEvent { .. } | Event::DeviceEvent { .. } = event {
+        let res = state.filter_event(ctx, &event);
+        if let Ok(false) = res {
+            return;
+        } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::FilterEvent) {
             return;
         }
+    }
 
-        *control_flow = ControlFlow::Poll;
+    // The boss key is swallowed whole, before it reaches the input contexts, so neither
+    // its press nor its release shows up as a keystroke anywhere.
+    if let Event::WindowEvent {
+        event:
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: key_state,
+                        virtual_keycode: keycode @ Some(_),
+                        ..
+                    },
+                ..
+            },
+        ..
+    } = &event
+    {
+        if *keycode == ctx.boss_key {
+            if *key_state == ElementState::Pressed {
+                ctx.gfx.window.set_minimized(true);
+            }
+            return;
+        }
+    }
 
-        process_event(ctx, &mut event);
-        match event {
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::Resized(logical_size) => {
-                    // let actual_size = logical_size;
-                    let res = state.resize_event(
-                        ctx,
-                        logical_size.width as f32,
-                        logical_size.height as f32,
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::CloseRequested => {
-                    let res = state.quit_event(ctx);
-                    if let Ok(false) = res {
-                        ctx.continuing = false;
-                    } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
-                        return;
-                    }
-                }
-                WindowEvent::Focused(gained) => {
-                    let res = state.focus_event(ctx, gained);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::FocusEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::ReceivedCharacter(ch) => {
-                    let res = state.text_input_event(ctx, ch);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextInputEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::ModifiersChanged(mods) => {
-                    ctx.keyboard.set_modifiers(KeyMods::from(mods))
-                }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: keycode,
-                            scancode,
-                            ..
-                        },
-                    ..
-                } => {
-                    let repeat = ctx.keyboard.is_key_repeated();
-                    let res = state.key_down_event(
-                        ctx,
-                        KeyInput {
-                            scancode,
-                            keycode,
-                            mods: ctx.keyboard.active_mods(),
-                        },
-                        repeat,
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyDownEvent) {
-                        return;
-                    };
+    ctx.event_outcome = EventOutcome::Ignored;
+    process_event(ctx, &mut event);
+    match event {
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::Resized(physical_size) => {
+                let res = state.resize_event(
+                    ctx,
+                    physical_size.width as f32,
+                    physical_size.height as f32,
+                );
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
+                    return;
+                };
+                ctx.resize_redraw = true;
+            }
+            WindowEvent::CloseRequested => {
+                let res = state.quit_event(ctx, QuitReason::WindowClose);
+                if let Ok(false) = res {
+                    ctx.continuing = false;
+                } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
+                    return;
                 }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Released,
-                            virtual_keycode: keycode,
-                            scancode,
-                            ..
-                        },
-                    ..
-                } => {
-                    let res = state.key_up_event(
-                        ctx,
-                        KeyInput {
+            }
+            WindowEvent::Focused(gained) => {
+                // Keys and buttons released while the window was unfocused never
+                // send an event to us, so release them now to keep them from getting stuck.
+                if !gained {
+                    for (scancode, keycode) in ctx.keyboard.release_all() {
+                        let input = KeyInput {
                             scancode,
                             keycode,
                             mods: ctx.keyboard.active_mods(),
-                        },
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyUpEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::MouseWheel { delta, .. } => {
-                    let (x, y) = match delta {
-                        MouseScrollDelta::LineDelta(x, y) => (x, y),
-                        MouseScrollDelta::PixelDelta(pos) => {
-                            let scale_factor = ctx.gfx.window.scale_factor();
-                            let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
-                            (x, y)
-                        }
-                    };
-                    let res = state.mouse_wheel_event(ctx, x, y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::MouseInput {
-                    state: element_state,
-                    button,
-                    ..
-                } => {
+                            timestamp: Some(ctx.event_timestamp),
+                        };
+                        ctx.input_events.push(InputEvent::KeyUp(input));
+                        let res = state.key_up_event(ctx, input);
+                        if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyUpEvent) {
+                            return;
+                        };
+                    }
                     let position = ctx.mouse.position();
-                    match element_state {
-                        ElementState::Pressed => {
-                            let res =
-                                state.mouse_button_down_event(ctx, button, position.x, position.y);
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::MouseButtonDownEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        ElementState::Released => {
-                            let res =
-                                state.mouse_button_up_event(ctx, button, position.x, position.y);
+                    for button in ctx.mouse.release_all() {
+                        ctx.input_events.push(InputEvent::MouseButtonUp {
+                            button,
+                            x: position.x,
+                            y: position.y,
+                        });
+                        let res = state.mouse_button_up_event(ctx, button, position.x, position.y);
+                        if catch_error(
+                            ctx,
+                            res,
+                            state,
+                            control_flow,
+                            ErrorOrigin::MouseButtonUpEvent,
+                        ) {
+                            return;
+                        };
+                    }
+                }
+                let res = state.focus_event(ctx, gained);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::FocusEvent) {
+                    return;
+                };
+            }
+            WindowEvent::ReceivedCharacter(ch) => {
+                let res = state.text_input_event(ctx, ch);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextInputEvent) {
+                    return;
+                };
+            }
+            WindowEvent::ModifiersChanged(mods) => ctx.keyboard.set_modifiers(KeyMods::from(mods)),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: keycode,
+                        scancode,
+                        ..
+                    },
+                ..
+            } => {
+                let repeat = ctx.keyboard.is_key_repeated();
+                let res = state.key_down_event(
+                    ctx,
+                    KeyInput {
+                        scancode,
+                        keycode,
+                        mods: ctx.keyboard.active_mods(),
+                        timestamp: Some(ctx.event_timestamp),
+                    },
+                    repeat,
+                );
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyDownEvent) {
+                    return;
+                };
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Released,
+                        virtual_keycode: keycode,
+                        scancode,
+                        ..
+                    },
+                ..
+            } => {
+                let res = state.key_up_event(
+                    ctx,
+                    KeyInput {
+                        scancode,
+                        keycode,
+                        mods: ctx.keyboard.active_mods(),
+                        timestamp: Some(ctx.event_timestamp),
+                    },
+                );
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyUpEvent) {
+                    return;
+                };
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    MouseScrollDelta::PixelDelta(pos) => {
+                        let scale_factor = ctx.gfx.window.scale_factor();
+                        let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
+                        (x, y)
+                    }
+                };
+                let res = state.mouse_wheel_event(ctx, x, y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
+                    return;
+                };
+            }
+            WindowEvent::MouseInput {
+                state: element_state,
+                button,
+                ..
+            } => {
+                let position = ctx.mouse.position();
+                if element_state == ElementState::Pressed && button == MouseButton::Left {
+                    let region = match state.window_hit_test(ctx, position.x, position.y) {
+                        Ok(region) => region,
+                        res @ Err(_) => {
                             if catch_error(
                                 ctx,
                                 res,
                                 state,
                                 control_flow,
-                                ErrorOrigin::MouseButtonUpEvent,
+                                ErrorOrigin::WindowHitTest,
                             ) {
                                 return;
-                            };
+                            }
+                            HitRegion::Client
                         }
-                    }
-                }
-                WindowEvent::CursorMoved { .. } => {
-                    let position = ctx.mouse.position();
-                    let delta = ctx.mouse.last_delta();
-                    let res =
-                        state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseMotionEvent) {
-                        return;
                     };
-                }
-                WindowEvent::Touch(touch) => {
-                    let res =
-                        state.touch_event(ctx, touch.phase, touch.location.x, touch.location.y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::TouchEvent) {
-                        return;
+                    let drag = match region {
+                        HitRegion::Client => None,
+                        HitRegion::Caption => Some(ctx.gfx.drag_window()),
+                        HitRegion::Resize(direction) => Some(ctx.gfx.drag_resize_window(direction)),
                     };
-                }
-                WindowEvent::CursorEntered { device_id: _ } => {
-                    let res = state.mouse_enter_or_leave(ctx, true);
-                    if catch_error(
-                        ctx,
-                        res,
-                        state,
-                        control_flow,
-                        ErrorOrigin::MouseEnterOrLeave,
-                    ) {
+                    if let Some(res) = drag {
+                        if let Err(e) = res {
+                            warn!("Could not drag the window: {e}");
+                        }
+                        // the window system takes over the drag and may swallow the release,
+                        // so the press is taken back from everywhere it was recorded
+                        ctx.mouse.set_button(button, false);
+                        ctx.input_events.retract(&InputEvent::MouseButtonDown {
+                            button,
+                            x: position.x,
+                            y: position.y,
+                        });
                         return;
                     }
                 }
-                WindowEvent::CursorLeft { device_id: _ } => {
-                    let res = state.mouse_enter_or_leave(ctx, false);
-                    if catch_error(
-                        ctx,
-                        res,
-                        state,
-                        control_flow,
-                        ErrorOrigin::MouseEnterOrLeave,
-                    ) {
-                        return;
+                match element_state {
+                    ElementState::Pressed => {
+                        let res =
+                            state.mouse_button_down_event(ctx, button, position.x, position.y);
+                        if catch_error(
+                            ctx,
+                            res,
+                            state,
+                            control_flow,
+                            ErrorOrigin::MouseButtonDownEvent,
+                        ) {
+                            return;
+                        };
                     }
-                }
-                _x => {
-                    // trace!("ignoring window event {:?}", x);
-                }
-            },
-            Event::DeviceEvent { .. } => (),
-            Event::Resumed => (),
-            Event::Suspended => (),
-            Event::NewEvents(_) => (),
-            Event::UserEvent(_) => (),
-            Event::MainEventsCleared => {
-                // If you are writing your own event loop, make sure
-                // you include `timer_context.tick()` and
-                // `ctx.process_event()` calls.  These update ggez's
-                // internal state however necessary.
-                ctx.time.tick();
-
-                // Handle gamepad events if necessary.
-                #[cfg(feature = "gamepad")]
-                while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad.next_event() {
-                    match event {
-                        gilrs::EventType::ButtonPressed(button, _) => {
-                            let res = state.gamepad_button_down_event(ctx, button, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadButtonDownEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        gilrs::EventType::ButtonReleased(button, _) => {
-                            let res = state.gamepad_button_up_event(ctx, button, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadButtonUpEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        gilrs::EventType::AxisChanged(axis, value, _) => {
-                            let res = state.gamepad_axis_event(ctx, axis, value, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadAxisEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        _ => {}
+                    ElementState::Released => {
+                        let res = state.mouse_button_up_event(ctx, button, position.x, position.y);
+                        if catch_error(
+                            ctx,
+                            res,
+                            state,
+                            control_flow,
+                            ErrorOrigin::MouseButtonUpEvent,
+                        ) {
+                            return;
+                        };
                     }
                 }
-
-                let res = state.update(ctx);
-                if catch_error(ctx, res, state, control_flow, ErrorOrigin::Update) {
+            }
+            WindowEvent::CursorMoved { .. } if ctx.mouse.coalesce_motion() => {
+                ctx.mouse.queue_motion();
+            }
+            WindowEvent::CursorMoved { .. } => {
+                let position = ctx.mouse.position();
+                let delta = ctx.mouse.last_delta();
+                let res = state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseMotionEvent) {
                     return;
                 };
-
-                if let Err(e) = ctx.gfx.begin_frame() {
-                    error!("Error on GraphicsContext::begin_frame(): {e:?}");
-                    eprintln!("Error on GraphicsContext::begin_frame(): {e:?}");
-                    *control_flow = ControlFlow::Exit;
+            }
+            WindowEvent::Touch(touch) => {
+                let res = state.touch_event(ctx, touch.phase, touch.location.x, touch.location.y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::TouchEvent) {
+                    return;
+                };
+            }
+            WindowEvent::CursorEntered { device_id: _ } => {
+                let res = state.mouse_enter_or_leave(ctx, true);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::MouseEnterOrLeave,
+                ) {
+                    return;
                 }
-
-                if let Err(e) = state.draw(ctx) {
-                    error!("Error on EventHandler::draw(): {e:?}");
-                    eprintln!("Error on EventHandler::draw(): {e:?}");
-                    if state.on_error(ctx, ErrorOrigin::Draw, e) {
-                        *control_flow = ControlFlow::Exit;
-                        return;
-                    }
+            }
+            WindowEvent::CursorLeft { device_id: _ } => {
+                let res = state.mouse_enter_or_leave(ctx, false);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::MouseEnterOrLeave,
+                ) {
+                    return;
                 }
+            }
+            WindowEvent::ThemeChanged(theme) => {
+                let res = state.theme_changed_event(ctx, theme);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::ThemeChangedEvent,
+                ) {
+                    return;
+                }
+            }
+            _x => {
+                // trace!("ignoring window event {:?}", x);
+            }
+        },
+        Event::DeviceEvent {
+            event: DeviceEvent::MouseMotion { .. },
+            ..
+        } => {
+            let delta = ctx.mouse.last_raw_delta();
+            let res = state.raw_mouse_motion_event(ctx, delta.x, delta.y);
+            if catch_error(
+                ctx,
+                res,
+                state,
+                control_flow,
+                ErrorOrigin::RawMouseMotionEvent,
+            ) {
+                return;
+            }
+        }
+        Event::DeviceEvent { .. } => (),
+        Event::Resumed => {
+            let _span = Span::enter("resumed");
+            let res = state.on_resume(ctx);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResumeEvent) {
+                return;
+            };
+        }
+        Event::Suspended => {
+            let _span = Span::enter("suspended");
+            let res = state.on_suspend(ctx);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::SuspendEvent) {
+                return;
+            };
+        }
+        Event::NewEvents(winit::event::StartCause::Init) if ctx.time.ticks() == 0 => {
+            let _span = Span::enter("starting");
+            // report the initial size once, so layout code only needs to live in `resize_event()`
+            let (width, height) = ctx.gfx.drawable_size();
+            let res = state.resize_event(ctx, width, height);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
+                return;
+            };
+        }
+        Event::NewEvents(_) => (),
+        Event::UserEvent(user_event) => {
+            let res = state.user_event(ctx, user_event);
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::UserEvent) {
+                return;
+            };
+        }
+        Event::MainEventsCleared => {
+            // If you are writing your own event loop, make sure
+            // you include `timer_context.tick()` and
+            // `ctx.process_event()` calls.  These update ggez's
+            // internal state however necessary.
+            let mut report = |ctx: &mut Context, state: &mut S, res, origin| {
+                catch_error(ctx, res, state, control_flow, origin)
+            };
+            if !update_frame(ctx, state, &mut report) {
+                return;
+            }
 
-                if let Err(e) = ctx.gfx.end_frame() {
-                    error!("Error on GraphicsContext::end_frame(): {e:?}");
-                    eprintln!("Error on GraphicsContext::end_frame(): {e:?}");
-                    *control_flow = ControlFlow::Exit;
+            if ctx.loop_mode != LoopMode::Poll || ctx.resize_redraw {
+                // let the window system coalesce the draw with any redraw it requested itself;
+                // while resizing, this keeps the drawn frame in step with the new surface size
+                ctx.gfx.window.request_redraw();
+                return;
+            }
+
+            if draw_frame(ctx, state, &mut report).is_err() {
+                exit_with_error(ctx, control_flow);
+            }
+        }
+        Event::RedrawRequested(_) => {
+            if (ctx.loop_mode != LoopMode::Poll || ctx.resize_redraw) && ctx.draw_enabled {
+                ctx.resize_redraw = false;
+                let mut report = |ctx: &mut Context, state: &mut S, res, origin| {
+                    catch_error(ctx, res, state, control_flow, origin)
+                };
+                if draw_frame(ctx, state, &mut report).is_err() {
+                    exit_with_error(ctx, control_flow);
                 }
+            }
+        }
+        Event::RedrawEventsCleared => (),
+        Event::LoopDestroyed => {
+            let _span = Span::enter("exiting");
+        }
+    }
+}
 
-                // reset the mouse delta for the next frame
-                // necessary because it's calculated cumulatively each cycle
-                ctx.mouse.reset_delta();
+/// A phase of the event loop, logged at trace level to the `ggez::event_loop` target when
+/// it is entered and left, if the `trace-event-loop` feature is enabled.
+struct Span {
+    #[cfg(feature = "trace-event-loop")]
+    phase: &'static str,
+    #[cfg(feature = "trace-event-loop")]
+    start: std::time::Instant,
+}
 
-                // Copy the state of the keyboard into the KeyboardContext
-                // and the mouse into the MouseContext
-                ctx.keyboard.save_keyboard_state();
-                ctx.mouse.save_mouse_state();
+impl Span {
+    #[cfg_attr(not(feature = "trace-event-loop"), allow(unused_variables))]
+    fn enter(phase: &'static str) -> Self {
+        #[cfg(feature = "trace-event-loop")]
+        {
+            trace!(target: "ggez::event_loop", "entering {phase}");
+            Span {
+                phase,
+                start: std::time::Instant::now(),
             }
-            Event::RedrawRequested(_) => (),
-            Event::RedrawEventsCleared => (),
-            Event::LoopDestroyed => (),
         }
-    })
+        #[cfg(not(feature = "trace-event-loop"))]
+        Span {}
+    }
+}
+
+#[cfg(feature = "trace-event-loop")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        trace!(
+            target: "ggez::event_loop",
+            "leaving {} after {:?}",
+            self.phase,
+            self.start.elapsed()
+        );
+    }
+}
+
+/// Runs the update half of a frame, as in the `MainEventsCleared` event: waits for the
+/// update rate cap, ticks the timer and calls the mouse motion, gamepad, scheduled event and
+/// update callbacks.
+///
+/// Every callback result is handed to `report`, which returns `true` if the frame has to be
+/// abandoned. Returns `true` if the frame should be drawn next; if drawing is disabled,
+/// the per-frame input state is reset here instead.
+pub(crate) fn update_frame<S, E, U, F>(ctx: &mut Context, state: &mut S, report: &mut F) -> bool
+where
+    S: EventHandler<E, U>,
+    E: std::fmt::Debug,
+    F: FnMut(&mut Context, &mut S, Result<(), E>, ErrorOrigin) -> bool,
+{
+    // If you are writing your own event loop, make sure
+    // you include `timer_context.tick()` and
+    // `ctx.process_event()` calls.  These update ggez's
+    // internal state however necessary.
+    let _span = Span::enter("frame");
+    let wait = ctx.time.time_until_next_update();
+    if !wait.is_zero() {
+        std::thread::sleep(wait);
+    }
+    ctx.time.tick();
+
+    if ctx.mouse.take_pending_motion() {
+        let position = ctx.mouse.position();
+        let delta = ctx.mouse.delta();
+        let res = state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+        if report(ctx, state, res, ErrorOrigin::MouseMotionEvent) {
+            return false;
+        }
+    }
+
+    // Handle gamepad events if necessary.
+    #[cfg(feature = "gamepad")]
+    while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad.next_event() {
+        let (res, origin) = match event {
+            gilrs::EventType::ButtonPressed(button, _) => (
+                state.gamepad_button_down_event(ctx, button, GamepadId(id)),
+                ErrorOrigin::GamepadButtonDownEvent,
+            ),
+            gilrs::EventType::ButtonReleased(button, _) => (
+                state.gamepad_button_up_event(ctx, button, GamepadId(id)),
+                ErrorOrigin::GamepadButtonUpEvent,
+            ),
+            gilrs::EventType::AxisChanged(axis, value, _) => {
+                let value = ctx.gamepad.apply_axis_response(axis, value);
+                (
+                    state.gamepad_axis_event(ctx, axis, value, GamepadId(id)),
+                    ErrorOrigin::GamepadAxisEvent,
+                )
+            }
+            _ => continue,
+        };
+        if report(ctx, state, res, origin) {
+            return false;
+        }
+    }
+
+    while let Some(id) = ctx.time.next_scheduled_event() {
+        let res = state.scheduled_event(ctx, id);
+        if report(ctx, state, res, ErrorOrigin::ScheduledEvent) {
+            return false;
+        }
+    }
+
+    let res = state.pre_update(ctx);
+    if report(ctx, state, res, ErrorOrigin::PreUpdate) {
+        return false;
+    }
+
+    let res = {
+        let _span = Span::enter("update");
+        state.update(ctx)
+    };
+    if report(ctx, state, res, ErrorOrigin::Update) {
+        return false;
+    }
+
+    if !ctx.draw_enabled {
+        crate::input::end_frame(ctx);
+        return false;
+    }
+    true
+}
+
+/// Draws a frame: `begin_frame()`, `draw()`, `post_draw()` and `end_frame()`,
+/// then resets the per-frame input state.
+///
+/// Callback results are handed to `report` like in [`update_frame()`]; once the frame has
+/// begun, it is always ended, even if `report` abandons it. Returns an error if the
+/// graphics context failed in a way that the game can't recover from.
+pub(crate) fn draw_frame<S, E, U, F>(ctx: &mut Context, state: &mut S, report: &mut F) -> GameResult
+where
+    S: EventHandler<E, U>,
+    E: std::fmt::Debug,
+    F: FnMut(&mut Context, &mut S, Result<(), E>, ErrorOrigin) -> bool,
+{
+    let _span = Span::enter("draw");
+    match ctx.gfx.begin_frame() {
+        Err(GameError::SurfaceError(
+            e @ (wgpu::SurfaceError::Lost
+            | wgpu::SurfaceError::Outdated
+            | wgpu::SurfaceError::Timeout),
+        )) => {
+            // not fatal; skip drawing this frame and try again on the next one
+            warn!("Skipping frame, could not acquire the window surface: {e}");
+            crate::input::end_frame(ctx);
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Error on GraphicsContext::begin_frame(): {e:?}");
+            eprintln!("Error on GraphicsContext::begin_frame(): {e:?}");
+            return Err(e);
+        }
+        Ok(()) => (),
+    }
+
+    draw_callbacks(ctx, state, report);
+
+    let res = ctx.gfx.end_frame();
+    if let Err(e) = &res {
+        error!("Error on GraphicsContext::end_frame(): {e:?}");
+        eprintln!("Error on GraphicsContext::end_frame(): {e:?}");
+    }
+
+    crate::input::end_frame(ctx);
+    res
+}
+
+/// Calls the drawing callbacks between `begin_frame()` and `end_frame()`, stopping at the
+/// first error `report` abandons the frame for.
+fn draw_callbacks<S, E, U, F>(ctx: &mut Context, state: &mut S, report: &mut F)
+where
+    S: EventHandler<E, U>,
+    E: std::fmt::Debug,
+    F: FnMut(&mut Context, &mut S, Result<(), E>, ErrorOrigin) -> bool,
+{
+    if !ctx.first_frame_started {
+        ctx.first_frame_started = true;
+        let res = state.first_frame_event(ctx);
+        if report(ctx, state, res, ErrorOrigin::FirstFrameEvent) {
+            return;
+        }
+    }
+
+    let res = state.draw(ctx);
+    if report(ctx, state, res, ErrorOrigin::Draw) {
+        return;
+    }
+
+    let res = state.post_draw(ctx);
+    let _ = report(ctx, state, res, ErrorOrigin::PostDraw);
 }
 
-fn catch_error<T, E, S: 'static>(
+fn catch_error<T, E, U, S: 'static>(
     ctx: &mut Context,
     event_result: Result<T, E>,
     state: &mut S,
@@ -585,47 +1208,99 @@ fn catch_error<T, E, S: 'static>(
     origin: ErrorOrigin,
 ) -> bool
 where
-    S: EventHandler<E>,
+    S: EventHandler<E, U>,
     E: std::fmt::Debug,
 {
     if let Err(e) = event_result {
         error!("Error on EventHandler {origin:?}: {e:?}");
         eprintln!("Error on EventHandler {origin:?}: {e:?}");
+        if let Some(hook) = &ctx.error_hook {
+            hook(origin, &e);
+        }
         if state.on_error(ctx, origin, e) {
-            *control_flow = ControlFlow::Exit;
+            exit_with_error(ctx, control_flow);
             return true;
         }
     }
     false
 }
 
+/// Ends the loop after a fatal error, with the exit code set by
+/// [`Context::request_quit_with_code()`](crate::Context::request_quit_with_code)
+/// if it isn't `0`, or with `1` otherwise, so the failure is seen by a parent process.
+fn exit_with_error(ctx: &mut Context, control_flow: &mut ControlFlow) {
+    if ctx.exit_code == 0 {
+        ctx.exit_code = 1;
+    }
+    *control_flow = ControlFlow::ExitWithCode(ctx.exit_code);
+}
+
 /// Feeds an `Event` into the `Context` so it can update any internal
 /// state it needs to, such as detecting window resizes.  If you are
 /// rolling your own event loop, you should call this on the events
 /// you receive before processing them yourself.
-pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
+pub fn process_event<U>(ctx: &mut Context, event: &mut winit::event::Event<U>) {
+    ctx.event_timestamp = std::time::Instant::now();
+
+    if let winit_event::Event::DeviceEvent {
+        event: winit_event::DeviceEvent::MouseMotion { delta: (dx, dy) },
+        ..
+    } = event
+    {
+        ctx.mouse.handle_raw_motion(*dx, *dy);
+    }
+
     if let winit_event::Event::WindowEvent { event, .. } = event {
         match event {
             winit_event::WindowEvent::Resized(physical_size) => {
+                if let Some(min_size) = ctx.min_logical_size {
+                    let scale_factor = ctx.gfx.window.scale_factor();
+                    let size = physical_size.to_logical::<f32>(scale_factor);
+                    if size.width < min_size.width || size.height < min_size.height {
+                        // request the window back up to the minimum size
+                        // and hide the undersized one from the game
+                        let clamped = dpi::LogicalSize::new(
+                            size.width.max(min_size.width),
+                            size.height.max(min_size.height),
+                        );
+                        ctx.gfx.window.set_inner_size(clamped);
+                        *physical_size = clamped.to_physical(scale_factor);
+                    }
+                }
                 ctx.gfx.resize(*physical_size);
             }
             winit_event::WindowEvent::CursorMoved {
                 position: physical_position,
                 ..
             } => {
-                ctx.mouse
-                    .handle_move(physical_position.x as f32, physical_position.y as f32);
+                process_cursor_moved(ctx, physical_position.x as f32, physical_position.y as f32);
             }
             winit_event::WindowEvent::MouseInput { button, state, .. } => {
-                let pressed = match state {
-                    winit_event::ElementState::Pressed => true,
-                    winit_event::ElementState::Released => false,
+                process_mouse_button(ctx, *button, *state == ElementState::Pressed);
+            }
+            winit_event::WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match *delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    MouseScrollDelta::PixelDelta(pos) => {
+                        let scale_factor = ctx.gfx.window.scale_factor();
+                        let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
+                        (x, y)
+                    }
                 };
-                ctx.mouse.set_button(*button, pressed);
+                ctx.input_events.push(InputEvent::MouseWheel { x, y });
+            }
+            winit_event::WindowEvent::ReceivedCharacter(ch) => {
+                ctx.input_events.push(InputEvent::TextInput(*ch));
             }
             winit_event::WindowEvent::ModifiersChanged(mods) => {
                 ctx.keyboard.set_modifiers(KeyMods::from(*mods))
             }
+            winit_event::WindowEvent::Focused(gained) => {
+                ctx.focused = *gained;
+                #[cfg(feature = "audio")]
+                ctx.audio.handle_focus(*gained);
+                ctx.input_events.push(InputEvent::Focus(*gained));
+            }
             winit_event::WindowEvent::KeyboardInput {
                 input:
                     winit::event::KeyboardInput {
@@ -636,14 +1311,7 @@ pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
                     },
                 ..
             } => {
-                let pressed = match state {
-                    winit_event::ElementState::Pressed => true,
-                    winit_event::ElementState::Released => false,
-                };
-                ctx.keyboard.set_scancode(*scancode, pressed);
-                if let Some(key) = keycode {
-                    ctx.keyboard.set_key(*key, pressed);
-                }
+                process_keystroke(ctx, *scancode, *keycode, *state == ElementState::Pressed);
             }
             winit_event::WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                 if !ctx.conf.window_mode.resize_on_scale_factor_change {
@@ -659,3 +1327,56 @@ pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
         }
     };
 }
+
+// The input a window event carries is applied by these, which `TestDriver` calls directly.
+// Each updates the input contexts and then records the input in the context's input event
+// queue, as seen by the callbacks.
+
+/// Applies a key being pressed or released.
+pub(crate) fn process_keystroke(
+    ctx: &mut Context,
+    scancode: ScanCode,
+    keycode: Option<KeyCode>,
+    pressed: bool,
+) {
+    ctx.keyboard.set_keystroke(scancode, keycode, pressed);
+    let input = KeyInput {
+        scancode,
+        keycode,
+        mods: ctx.keyboard.active_mods(),
+        timestamp: Some(ctx.event_timestamp),
+    };
+    ctx.input_events.push(if pressed {
+        InputEvent::KeyDown {
+            input,
+            repeat: ctx.keyboard.is_key_repeated(),
+        }
+    } else {
+        InputEvent::KeyUp(input)
+    });
+}
+
+/// Applies the mouse cursor moving to the given position, in physical pixels.
+pub(crate) fn process_cursor_moved(ctx: &mut Context, x: f32, y: f32) {
+    ctx.mouse.handle_move(x, y);
+    let position = ctx.mouse.position();
+    let delta = ctx.mouse.last_delta();
+    ctx.input_events.push(InputEvent::MouseMotion {
+        x: position.x,
+        y: position.y,
+        dx: delta.x,
+        dy: delta.y,
+    });
+}
+
+/// Applies a mouse button being pressed or released at the current cursor position.
+pub(crate) fn process_mouse_button(ctx: &mut Context, button: MouseButton, pressed: bool) {
+    ctx.mouse.set_button(button, pressed);
+    let position = ctx.mouse.position();
+    let (x, y) = (position.x, position.y);
+    ctx.input_events.push(if pressed {
+        InputEvent::MouseButtonDown { button, x, y }
+    } else {
+        InputEvent::MouseButtonUp { button, x, y }
+    });
+}
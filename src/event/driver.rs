@@ -0,0 +1,236 @@
+use super::{EventHandler, EventOutcome, KeyInput, MouseButton, ScanCode};
+use crate::context::Context;
+use crate::input::keyboard::KeyCode;
+use crate::input::InputEvent;
+use crate::GameError;
+
+/// Drives an [`EventHandler`](trait.EventHandler.html) by hand, without running
+/// the real event loop.
+///
+/// Every input method updates the input contexts and the input event queue the same
+/// way [`process_event()`](fn.process_event.html) does for the matching window event
+/// in [`run()`](fn.run.html), and then calls the matching callback on the handler.
+/// There is no keyboard behind the driver, so each key is sent with a made-up
+/// scancode that is different for every key. [`tick()`](#method.tick) runs one
+/// iteration of the main loop: scheduled events, `update()`, `draw()` and the
+/// end-of-frame bookkeeping.
+///
+/// Unlike `run()`, errors are not passed to
+/// [`on_error()`](trait.EventHandler.html#method.on_error) but returned to the
+/// caller, so a test can `?` or `unwrap()` them.
+///
+/// The `Context` still needs a window and a graphics device, since the graphics
+/// context is built around the window's surface, but it can be a hidden window
+/// (see [`WindowMode::visible()`](../conf/struct.WindowMode.html#method.visible)).
+/// Keep the `EventLoop` returned by [`ContextBuilder::build()`](../struct.ContextBuilder.html#method.build)
+/// alive for as long as the driver is in use.
+///
+/// ```rust,no_run
+/// # use ggez::{event::{EventHandler, TestDriver}, input::keyboard::KeyCode, Context, GameResult};
+/// # struct Game;
+/// # impl EventHandler for Game {
+/// #     fn update(&mut self, _ctx: &mut Context) -> GameResult { Ok(()) }
+/// #     fn draw(&mut self, _ctx: &mut Context) -> GameResult { Ok(()) }
+/// # }
+/// # fn t() -> GameResult {
+/// let (ctx, _event_loop) = ggez::ContextBuilder::new("test", "ggez")
+///     .window_mode(ggez::conf::WindowMode::default().visible(false))
+///     .build()?;
+/// let mut driver = TestDriver::new(ctx, Game);
+/// driver.press_key(KeyCode::Space)?;
+/// driver.tick()?;
+/// driver.assert_key_pressed(KeyCode::Space);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TestDriver<S, E = GameError> {
+    ctx: Context,
+    state: S,
+    _error: std::marker::PhantomData<E>,
+}
+
+impl<S, E> TestDriver<S, E>
+where
+    S: EventHandler<E>,
+    E: std::fmt::Debug,
+{
+    /// Creates a new `TestDriver` for the given context and event handler.
+    pub fn new(ctx: Context, state: S) -> Self {
+        Self {
+            ctx,
+            state,
+            _error: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns the driven `Context`.
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// Returns the driven `Context` mutably.
+    pub fn ctx_mut(&mut self) -> &mut Context {
+        &mut self.ctx
+    }
+
+    /// Returns the driven event handler.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the driven event handler mutably.
+    pub fn state_mut(&mut self) -> &mut S {
+        &mut self.state
+    }
+
+    /// Consumes the driver, returning the context and the event handler.
+    pub fn into_inner(self) -> (Context, S) {
+        (self.ctx, self.state)
+    }
+
+    /// Presses the given key, calling `key_down_event()`.
+    pub fn press_key(&mut self, key: KeyCode) -> Result<(), E> {
+        let repeated = self.ctx.keyboard.is_key_pressed(key);
+        self.begin_event();
+        super::process_keystroke(&mut self.ctx, scancode(key), Some(key), true);
+        let input = self.key_input(key);
+        self.state.key_down_event(&mut self.ctx, input, repeated)
+    }
+
+    /// Releases the given key, calling `key_up_event()`.
+    pub fn release_key(&mut self, key: KeyCode) -> Result<(), E> {
+        self.begin_event();
+        super::process_keystroke(&mut self.ctx, scancode(key), Some(key), false);
+        let input = self.key_input(key);
+        self.state.key_up_event(&mut self.ctx, input)
+    }
+
+    /// Types the given character, calling `text_input_event()`.
+    pub fn type_char(&mut self, character: char) -> Result<(), E> {
+        self.begin_event();
+        self.ctx.input_events.push(InputEvent::TextInput(character));
+        self.state.text_input_event(&mut self.ctx, character)
+    }
+
+    /// Moves the mouse cursor to the given position (in physical pixels),
+    /// calling `mouse_motion_event()`, or deferring it to the next
+    /// [`tick()`](#method.tick) if motion is coalesced.
+    pub fn move_mouse(&mut self, x: f32, y: f32) -> Result<(), E> {
+        self.begin_event();
+        super::process_cursor_moved(&mut self.ctx, x, y);
+        if self.ctx.mouse.coalesce_motion() {
+            self.ctx.mouse.queue_motion();
+            return Ok(());
+        }
+        let position = self.ctx.mouse.position();
+        let delta = self.ctx.mouse.last_delta();
+        self.state
+            .mouse_motion_event(&mut self.ctx, position.x, position.y, delta.x, delta.y)
+    }
+
+    /// Presses the given mouse button at the current cursor position,
+    /// calling `mouse_button_down_event()`.
+    pub fn press_mouse(&mut self, button: MouseButton) -> Result<(), E> {
+        self.begin_event();
+        super::process_mouse_button(&mut self.ctx, button, true);
+        let position = self.ctx.mouse.position();
+        self.state
+            .mouse_button_down_event(&mut self.ctx, button, position.x, position.y)
+    }
+
+    /// Releases the given mouse button at the current cursor position,
+    /// calling `mouse_button_up_event()`.
+    pub fn release_mouse(&mut self, button: MouseButton) -> Result<(), E> {
+        self.begin_event();
+        super::process_mouse_button(&mut self.ctx, button, false);
+        let position = self.ctx.mouse.position();
+        self.state
+            .mouse_button_up_event(&mut self.ctx, button, position.x, position.y)
+    }
+
+    /// Scrolls the mouse wheel by the given number of lines,
+    /// calling `mouse_wheel_event()`.
+    pub fn scroll(&mut self, x: f32, y: f32) -> Result<(), E> {
+        self.begin_event();
+        self.ctx.input_events.push(InputEvent::MouseWheel { x, y });
+        self.state.mouse_wheel_event(&mut self.ctx, x, y)
+    }
+
+    /// Runs one iteration of the main loop, the same way [`run()`](fn.run.html) does
+    /// when all pending window events have been handled.
+    ///
+    /// The first error returned by a callback stops the frame and is returned, but a frame
+    /// that has begun drawing is still ended first, so the next `tick()` can draw again.
+    pub fn tick(&mut self) -> Result<(), E> {
+        let mut error = None;
+        let mut report = |_: &mut Context, _: &mut S, res: Result<(), E>, _| match res {
+            Ok(()) => false,
+            Err(e) => {
+                error = Some(e);
+                true
+            }
+        };
+        if super::update_frame(&mut self.ctx, &mut self.state, &mut report) {
+            // there is no window system to coalesce redraws with, so draw right away
+            // whatever the loop mode; graphics errors have already been logged
+            let _ = super::draw_frame(&mut self.ctx, &mut self.state, &mut report);
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Panics if the given key is not currently held down.
+    #[track_caller]
+    pub fn assert_key_pressed(&self, key: KeyCode) {
+        assert!(
+            self.ctx.keyboard.is_key_pressed(key),
+            "expected {key:?} to be pressed"
+        );
+    }
+
+    /// Panics if the given mouse button is not currently held down.
+    #[track_caller]
+    pub fn assert_mouse_pressed(&self, button: MouseButton) {
+        assert!(
+            self.ctx.mouse.button_pressed(button),
+            "expected {button:?} to be pressed"
+        );
+    }
+
+    /// Panics if the mouse cursor is not at the given position.
+    #[track_caller]
+    pub fn assert_mouse_position(&self, x: f32, y: f32) {
+        let position = self.ctx.mouse.position();
+        assert!(
+            position.x == x && position.y == y,
+            "expected the mouse at ({x}, {y}), but it is at ({}, {})",
+            position.x,
+            position.y
+        );
+    }
+
+    /// Prepares the context for dispatching a new event, like `process_event()` does.
+    fn begin_event(&mut self) {
+        self.ctx.event_outcome = EventOutcome::Ignored;
+        self.ctx.event_timestamp = std::time::Instant::now();
+    }
+
+    fn key_input(&self, key: KeyCode) -> KeyInput {
+        KeyInput {
+            scancode: scancode(key),
+            keycode: Some(key),
+            mods: self.ctx.keyboard.active_mods(),
+            timestamp: Some(self.ctx.event_timestamp),
+        }
+    }
+}
+
+impl<S, E> std::fmt::Debug for TestDriver<S, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<TestDriver: {:p}>", self)
+    }
+}
+
+/// The made-up scancode sent for the given key.
+fn scancode(key: KeyCode) -> ScanCode {
+    key as ScanCode + 1
+}
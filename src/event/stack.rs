@@ -0,0 +1,396 @@
+#[cfg(feature = "gamepad")]
+use super::{Axis, Button, GamepadId};
+use super::{
+    ErrorOrigin, Event, EventHandler, EventOutcome, HitRegion, KeyInput, MouseButton, QuitReason,
+    Theme, TouchPhase,
+};
+use crate::context::Context;
+use crate::GameError;
+
+/// A stack of event handlers which itself is an [`EventHandler`](trait.EventHandler.html).
+///
+/// This is the classic scene/overlay stack: push your game world first and
+/// a UI on top of it, and the UI gets the first chance to react to input.
+///
+/// The callbacks are dispatched as follows:
+///
+/// - Input events (mouse, keyboard, text, touch and gamepad) are passed to the
+///   handlers from the top of the stack down, stopping at the first handler that
+///   reports the event as handled with
+///   [`Context::set_event_outcome()`](crate::Context::set_event_outcome).
+/// - [`update()`](trait.EventHandler.html#tymethod.update) and
+///   [`draw()`](trait.EventHandler.html#tymethod.draw) run on all handlers from the
+///   bottom of the stack up, so overlays are drawn on top of what's below them.
+///   The same goes for all other notifications (resizing, focus changes, etc).
+/// - [`quit_event()`](trait.EventHandler.html#method.quit_event) is passed from the top
+///   down, stopping at the first handler that cancels quitting.
+///   [`filter_event()`](trait.EventHandler.html#method.filter_event) works the same way,
+///   dropping the event as soon as one handler drops it.
+/// - [`user_event()`](trait.EventHandler.html#method.user_event) and
+///   [`on_error()`](trait.EventHandler.html#method.on_error) are passed to the topmost
+///   handler only. If the stack is empty, all errors are fatal.
+///
+/// An error returned by any handler stops the dispatch and is returned right away.
+pub struct HandlerStack<E = GameError, U = ()> {
+    handlers: Vec<Box<dyn EventHandler<E, U>>>,
+}
+
+impl<E, U> HandlerStack<E, U>
+where
+    E: std::fmt::Debug,
+{
+    /// Creates a new, empty `HandlerStack`.
+    pub fn new() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Pushes a handler onto the top of the stack.
+    pub fn push(&mut self, handler: Box<dyn EventHandler<E, U>>) {
+        self.handlers.push(handler);
+    }
+
+    /// Removes the topmost handler from the stack and returns it.
+    pub fn pop(&mut self) -> Option<Box<dyn EventHandler<E, U>>> {
+        self.handlers.pop()
+    }
+
+    /// Returns the number of handlers on the stack.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Returns whether or not the stack is empty.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Passes an input event from the top of the stack down,
+    /// until one of the handlers reports it as handled.
+    fn dispatch_top_down<F>(&mut self, ctx: &mut Context, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&mut dyn EventHandler<E, U>, &mut Context) -> Result<(), E>,
+    {
+        let _ = top_down(&mut self.handlers, |handler| {
+            ctx.set_event_outcome(EventOutcome::Ignored);
+            f(handler.as_mut(), ctx)?;
+            Ok(ctx.event_outcome() == EventOutcome::Handled)
+        })?;
+        Ok(())
+    }
+
+    /// Passes a callback to all handlers, from the bottom of the stack up.
+    fn dispatch_bottom_up<F>(&mut self, ctx: &mut Context, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&mut dyn EventHandler<E, U>, &mut Context) -> Result<(), E>,
+    {
+        bottom_up(&mut self.handlers, |handler| f(handler.as_mut(), ctx))
+    }
+}
+
+/// Calls `f` on the layers from the top down, until it returns `true`.
+/// Returns whether any layer did.
+fn top_down<T, E>(
+    layers: &mut [T],
+    mut f: impl FnMut(&mut T) -> Result<bool, E>,
+) -> Result<bool, E> {
+    for layer in layers.iter_mut().rev() {
+        if f(layer)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Calls `f` on every layer, from the bottom up.
+fn bottom_up<T, E>(layers: &mut [T], f: impl FnMut(&mut T) -> Result<(), E>) -> Result<(), E> {
+    layers.iter_mut().try_for_each(f)
+}
+
+/// Calls `f` on the topmost layer only, or returns `empty` if there is none.
+fn topmost<T, R>(layers: &mut [T], empty: R, f: impl FnOnce(&mut T) -> R) -> R {
+    match layers.last_mut() {
+        Some(layer) => f(layer),
+        None => empty,
+    }
+}
+
+impl<E, U> Default for HandlerStack<E, U>
+where
+    E: std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, U> std::fmt::Debug for HandlerStack<E, U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<HandlerStack: {} handlers>", self.handlers.len())
+    }
+}
+
+impl<E, U> EventHandler<E, U> for HandlerStack<E, U>
+where
+    E: std::fmt::Debug,
+{
+    fn update(&mut self, ctx: &mut Context) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.update(ctx))
+    }
+
+    fn pre_update(&mut self, ctx: &mut Context) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.pre_update(ctx))
+    }
+
+    fn first_frame_event(&mut self, ctx: &mut Context) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.first_frame_event(ctx))
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.draw(ctx))
+    }
+
+    fn post_draw(&mut self, ctx: &mut Context) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.post_draw(ctx))
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.mouse_button_down_event(ctx, button, x, y))
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.mouse_button_up_event(ctx, button, x, y))
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.mouse_motion_event(ctx, x, y, dx, dy))
+    }
+
+    fn raw_mouse_motion_event(&mut self, ctx: &mut Context, dx: f64, dy: f64) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.raw_mouse_motion_event(ctx, dx, dy))
+    }
+
+    fn mouse_enter_or_leave(&mut self, ctx: &mut Context, entered: bool) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.mouse_enter_or_leave(ctx, entered))
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, x: f32, y: f32) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.mouse_wheel_event(ctx, x, y))
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        repeated: bool,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.key_down_event(ctx, input, repeated))
+    }
+
+    fn key_up_event(&mut self, ctx: &mut Context, input: KeyInput) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.key_up_event(ctx, input))
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.text_input_event(ctx, character))
+    }
+
+    fn touch_event(
+        &mut self,
+        ctx: &mut Context,
+        phase: TouchPhase,
+        x: f64,
+        y: f64,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.touch_event(ctx, phase, x, y))
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        btn: Button,
+        id: GamepadId,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.gamepad_button_down_event(ctx, btn, id))
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn gamepad_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        btn: Button,
+        id: GamepadId,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.gamepad_button_up_event(ctx, btn, id))
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn gamepad_axis_event(
+        &mut self,
+        ctx: &mut Context,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) -> Result<(), E> {
+        self.dispatch_top_down(ctx, |h, ctx| h.gamepad_axis_event(ctx, axis, value, id))
+    }
+
+    fn scheduled_event(&mut self, ctx: &mut Context, id: u64) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.scheduled_event(ctx, id))
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.focus_event(ctx, gained))
+    }
+
+    fn theme_changed_event(&mut self, ctx: &mut Context, theme: Theme) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.theme_changed_event(ctx, theme))
+    }
+
+    fn on_suspend(&mut self, ctx: &mut Context) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.on_suspend(ctx))
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.on_resume(ctx))
+    }
+
+    fn user_event(&mut self, ctx: &mut Context, event: U) -> Result<(), E> {
+        topmost(&mut self.handlers, Ok(()), |h| h.user_event(ctx, event))
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context, reason: QuitReason) -> Result<bool, E> {
+        top_down(&mut self.handlers, |h| h.quit_event(ctx, reason))
+    }
+
+    fn filter_event(&mut self, ctx: &mut Context, event: &Event<U>) -> Result<bool, E> {
+        let dropped = top_down(&mut self.handlers, |h| Ok(!h.filter_event(ctx, event)?))?;
+        Ok(!dropped)
+    }
+
+    fn window_hit_test(&mut self, ctx: &mut Context, x: f32, y: f32) -> Result<HitRegion, E> {
+        for handler in self.handlers.iter_mut().rev() {
+            let region = handler.window_hit_test(ctx, x, y)?;
+            if region != HitRegion::Client {
+                return Ok(region);
+            }
+        }
+        Ok(HitRegion::Client)
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) -> Result<(), E> {
+        self.dispatch_bottom_up(ctx, |h, ctx| h.resize_event(ctx, width, height))
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, origin: ErrorOrigin, e: E) -> bool {
+        topmost(&mut self.handlers, true, |h| h.on_error(ctx, origin, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The dispatch rules are checked on plain layers, since a `Context` needs a window.
+    struct Layer {
+        name: &'static str,
+        handles_input: bool,
+        cancels_quit: bool,
+    }
+
+    const LAYERS: [Layer; 3] = [
+        Layer {
+            name: "world",
+            handles_input: true,
+            cancels_quit: false,
+        },
+        Layer {
+            name: "menu",
+            handles_input: true,
+            cancels_quit: true,
+        },
+        Layer {
+            name: "hud",
+            handles_input: false,
+            cancels_quit: false,
+        },
+    ];
+
+    #[test]
+    fn headless_input_stops_at_first_handler() {
+        let mut layers = LAYERS;
+        let mut log = Vec::new();
+        let handled = top_down(&mut layers, |layer| {
+            log.push(layer.name);
+            Ok::<_, ()>(layer.handles_input)
+        });
+        assert_eq!(handled, Ok(true));
+        assert_eq!(log, ["hud", "menu"]);
+    }
+
+    #[test]
+    fn headless_updates_run_bottom_up() {
+        let mut layers = LAYERS;
+        let mut log = Vec::new();
+        bottom_up(&mut layers, |layer| {
+            log.push(layer.name);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        assert_eq!(log, ["world", "menu", "hud"]);
+
+        // an error stops the dispatch
+        log.clear();
+        let res = bottom_up(&mut layers, |layer| {
+            log.push(layer.name);
+            if layer.name == "menu" {
+                Err("menu failed")
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(res, Err("menu failed"));
+        assert_eq!(log, ["world", "menu"]);
+    }
+
+    #[test]
+    fn headless_quit_is_vetoed_from_the_top() {
+        let mut layers = LAYERS;
+        let mut log = Vec::new();
+        let cancelled = top_down(&mut layers, |layer| {
+            log.push(layer.name);
+            Ok::<_, ()>(layer.cancels_quit)
+        });
+        assert_eq!(cancelled, Ok(true));
+        assert_eq!(log, ["hud", "menu"]);
+    }
+
+    #[test]
+    fn headless_errors_go_to_the_topmost_layer() {
+        let mut layers = LAYERS;
+        assert_eq!(topmost(&mut layers, "none", |layer| layer.name), "hud");
+        // with nothing left to handle them, errors are fatal
+        assert!(topmost(&mut [] as &mut [Layer], true, |_| false));
+    }
+}
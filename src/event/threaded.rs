@@ -0,0 +1,160 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::{GameError, GameResult};
+
+/// Game state that is stepped on a background thread by [`ThreadedUpdate`](struct.ThreadedUpdate.html).
+///
+/// It doesn't get to see the [`Context`](crate::Context), which has to stay on the main
+/// thread; everything it needs from there, like the player's input, is passed in as `Input`.
+pub trait Simulation: Send + 'static {
+    /// What the main thread passes to every step, e.g. the buttons held this frame.
+    type Input: Send + 'static;
+    /// What `draw()` gets to see of the state.
+    type Snapshot: Send + 'static;
+
+    /// Advances the simulation by `dt`.
+    fn step(&mut self, input: Self::Input, dt: Duration);
+
+    /// Copies out whatever is needed to draw the current state.
+    fn snapshot(&self) -> Self::Snapshot;
+}
+
+/// Runs a [`Simulation`](trait.Simulation.html) on a worker thread, so CPU-heavy game logic
+/// can use another core while the main thread draws.
+///
+/// Call [`sync()`](#method.sync) once per frame in your
+/// [`update()`](trait.EventHandler.html#tymethod.update), and draw the
+/// [`snapshot()`](#method.snapshot) in [`draw()`](trait.EventHandler.html#tymethod.draw).
+/// The two threads work on alternating buffers: while the worker computes the step
+/// started by the current frame's `sync()`, `draw()` shows the snapshot of the step before,
+/// so what is drawn is always one step behind. `sync()` is the only point where the threads
+/// wait for each other, so a step that takes longer than a frame slows the game down.
+///
+/// ```rust,no_run
+/// # use ggez::event::{Simulation, ThreadedUpdate};
+/// # use std::time::Duration;
+/// struct World { x: f32 }
+///
+/// impl Simulation for World {
+///     type Input = f32;
+///     type Snapshot = f32;
+///
+///     fn step(&mut self, speed: f32, dt: Duration) {
+///         self.x += speed * dt.as_secs_f32();
+///     }
+///
+///     fn snapshot(&self) -> f32 {
+///         self.x
+///     }
+/// }
+///
+/// # fn t(ctx: &ggez::Context) -> ggez::GameResult {
+/// let mut world = ThreadedUpdate::new(World { x: 0.0 });
+/// // in update()
+/// world.sync(10.0, ctx.time.delta())?;
+/// // in draw()
+/// let x = *world.snapshot();
+/// # Ok(())
+/// # }
+/// ```
+pub struct ThreadedUpdate<S: Simulation> {
+    steps: mpsc::Sender<(S::Input, Duration)>,
+    snapshots: mpsc::Receiver<S::Snapshot>,
+    snapshot: S::Snapshot,
+    stepping: bool,
+    worker: thread::JoinHandle<S>,
+}
+
+impl<S: Simulation> ThreadedUpdate<S> {
+    /// Moves the simulation to a new worker thread.
+    pub fn new(mut simulation: S) -> Self {
+        let snapshot = simulation.snapshot();
+        let (steps, step_rx) = mpsc::channel::<(S::Input, Duration)>();
+        let (snapshot_tx, snapshots) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            for (input, dt) in step_rx {
+                simulation.step(input, dt);
+                if snapshot_tx.send(simulation.snapshot()).is_err() {
+                    break;
+                }
+            }
+            simulation
+        });
+        ThreadedUpdate {
+            steps,
+            snapshots,
+            snapshot,
+            stepping: false,
+            worker,
+        }
+    }
+
+    /// Waits for the previous step to finish, making its result the new
+    /// [`snapshot()`](#method.snapshot), then starts the next step with the given input.
+    ///
+    /// Returns an error if the simulation panicked.
+    pub fn sync(&mut self, input: S::Input, dt: Duration) -> GameResult {
+        if self.stepping {
+            self.snapshot = self.snapshots.recv().map_err(|_| stopped())?;
+        }
+        self.steps.send((input, dt)).map_err(|_| stopped())?;
+        self.stepping = true;
+        Ok(())
+    }
+
+    /// Returns the state as of the last finished step.
+    pub fn snapshot(&self) -> &S::Snapshot {
+        &self.snapshot
+    }
+
+    /// Waits for the current step to finish and returns the simulation.
+    ///
+    /// Returns an error if the simulation panicked.
+    pub fn into_inner(self) -> GameResult<S> {
+        drop(self.steps);
+        self.worker.join().map_err(|_| stopped())
+    }
+}
+
+impl<S: Simulation> std::fmt::Debug for ThreadedUpdate<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<ThreadedUpdate: {self:p}>")
+    }
+}
+
+fn stopped() -> GameError {
+    GameError::EventLoopError(String::from("The simulation thread panicked"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(u32);
+
+    impl Simulation for Counter {
+        type Input = u32;
+        type Snapshot = u32;
+
+        fn step(&mut self, input: u32, _dt: Duration) {
+            self.0 += input;
+        }
+
+        fn snapshot(&self) -> u32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn snapshots_lag_one_step() {
+        let mut counter = ThreadedUpdate::new(Counter(0));
+        assert_eq!(*counter.snapshot(), 0);
+        counter.sync(1, Duration::ZERO).unwrap();
+        assert_eq!(*counter.snapshot(), 0);
+        counter.sync(2, Duration::ZERO).unwrap();
+        assert_eq!(*counter.snapshot(), 1);
+        assert_eq!(counter.into_inner().unwrap().0, 3);
+    }
+}
@@ -251,6 +251,13 @@ impl Filesystem {
             })
     }
 
+    /// Returns where the file at the given path is located on disk,
+    /// or `None` if it doesn't exist or is inside of a zip file.
+    #[cfg(feature = "audio")]
+    pub(crate) fn physical_path<P: AsRef<path::Path>>(&self, path: P) -> Option<path::PathBuf> {
+        self.vfs().physical_path(path.as_ref())
+    }
+
     /// Creates a new file in the user directory and opens it
     /// to be written to, truncating it if it already exists.
     pub fn create<P: AsRef<path::Path>>(&self, path: P) -> GameResult<File> {
@@ -18,7 +18,7 @@ use super::{
 };
 use crate::{
     conf::{self, Backend, Conf, FullscreenType, WindowMode},
-    context::Has,
+    context::{Has, HasMut},
     error::GameResult,
     filesystem::{Filesystem, InternalClone},
     graphics::gpu::{bind_group::BindGroupLayoutBuilder, pipeline::RenderPipelineInfo},
@@ -59,6 +59,20 @@ pub struct WgpuContext {
     pub queue: wgpu::Queue,
 }
 
+/// The GPU memory used by buffers and textures, see
+/// [`GraphicsContext::memory_stats()`](struct.GraphicsContext.html#method.memory_stats).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct GpuMemoryStats {
+    /// The number of textures, i.e. images, canvases and the text glyph cache.
+    pub texture_count: u64,
+    /// The estimated size of all textures, in bytes.
+    pub texture_bytes: u64,
+    /// The number of buffers, i.e. meshes, instance arrays and internal buffers.
+    pub buffer_count: u64,
+    /// The size of all buffers, in bytes.
+    pub buffer_bytes: u64,
+}
+
 /// A concrete graphics context for WGPU rendering.
 #[allow(missing_debug_implementations)]
 pub struct GraphicsContext {
@@ -66,6 +80,9 @@ pub struct GraphicsContext {
 
     pub(crate) window: winit::window::Window,
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
+    pub(crate) alpha_modes: Vec<wgpu::CompositeAlphaMode>,
+    pub(crate) surface_formats: Vec<ImageFormat>,
+    pub(crate) adapter_info: wgpu::AdapterInfo,
 
     pub(crate) bind_group_cache: BindGroupCache,
     pub(crate) pipeline_cache: PipelineCache,
@@ -100,9 +117,9 @@ pub struct GraphicsContext {
 impl GraphicsContext {
     #[allow(unsafe_code)]
     /// Create a new graphics context
-    pub fn new(
+    pub fn new<U>(
         game_id: &str,
-        event_loop: &winit::event_loop::EventLoop<()>,
+        event_loop: &winit::event_loop::EventLoop<U>,
         conf: &Conf,
         filesystem: &Filesystem,
     ) -> GameResult<Self> {
@@ -122,7 +139,9 @@ impl GraphicsContext {
                 filesystem,
             ) {
                 Ok(o) => Ok(o),
-                Err(GameError::GraphicsInitializationError) => {
+                Err(
+                    GameError::GraphicsInitializationError | GameError::SurfaceCreationError(_),
+                ) => {
                     println!(
                         "Failed to initialize graphics, trying secondary backends.. Please mention this if you encounter any bugs!"
                     );
@@ -152,7 +171,23 @@ impl GraphicsContext {
                 Backend::BrowserWebGpu => wgpu::Backends::BROWSER_WEBGPU,
             });
 
-            Self::new_from_instance(game_id, instance, event_loop, conf, filesystem)
+            match Self::new_from_instance(game_id, instance, event_loop, conf, filesystem) {
+                Ok(o) => Ok(o),
+                Err(
+                    GameError::GraphicsInitializationError | GameError::SurfaceCreationError(_),
+                ) => {
+                    warn!(
+                        "Failed to initialize graphics with the {:?} backend, falling back to any available backend",
+                        conf.backend
+                    );
+                    let conf = Conf {
+                        backend: Backend::All,
+                        ..conf.clone()
+                    };
+                    Self::new(game_id, event_loop, &conf, filesystem)
+                }
+                Err(e) => Err(e),
+            }
         }
     }
 
@@ -228,10 +263,10 @@ impl GraphicsContext {
     }
 
     #[allow(unsafe_code)]
-    pub(crate) fn new_from_instance(
+    pub(crate) fn new_from_instance<U>(
         #[allow(unused_variables)] game_id: &str,
         instance: wgpu::Instance,
-        event_loop: &winit::event_loop::EventLoop<()>,
+        event_loop: &winit::event_loop::EventLoop<U>,
         conf: &Conf,
         filesystem: &Filesystem,
     ) -> GameResult<Self> {
@@ -274,8 +309,7 @@ impl GraphicsContext {
         };
 
         let window = window_builder.build(event_loop)?;
-        let surface = unsafe { instance.create_surface(&window) }
-            .map_err(|_| GameError::GraphicsInitializationError)?;
+        let surface = unsafe { instance.create_surface(&window) }?;
 
         let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::HighPerformance,
@@ -329,7 +363,8 @@ impl GraphicsContext {
             } else {
                 wgpu::PresentMode::AutoNoVsync
             },
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            alpha_mode: surface_alpha_mode(&capabilities.alpha_modes, conf.window_mode.transparent)
+                .unwrap_or(wgpu::CompositeAlphaMode::Auto),
             view_formats: vec![],
         };
 
@@ -444,6 +479,9 @@ impl GraphicsContext {
 
             window,
             surface_config,
+            alpha_modes: capabilities.alpha_modes,
+            surface_formats: capabilities.formats,
+            adapter_info: adapter.get_info(),
 
             bind_group_cache,
             pipeline_cache,
@@ -495,6 +533,22 @@ impl GraphicsContext {
     }
 
     /// Returns a reference to the underlying WGPU context.
+    ///
+    /// Its `device` and `queue` can be used to create your own buffers, textures and
+    /// pipelines, for rendering that the built-in `Canvas` API can't express. Render targets
+    /// for pipelines that draw to the screen need to use [`surface_format()`](#method.surface_format).
+    ///
+    /// Keep in mind how your commands are ordered relative to ggez's:
+    ///
+    /// - ggez records all drawing of a frame into the command encoder returned by
+    ///   [`commands()`](#method.commands), which only exists between `begin_frame()` and
+    ///   `end_frame()` (that is, during `draw()`). Encoding your passes there puts them
+    ///   in order with canvases that were finished before them.
+    /// - That encoder is only submitted in `end_frame()`, so anything submitted directly
+    ///   through `queue` (including `write_buffer()` and `write_texture()`) executes before
+    ///   *all* of the frame's drawing, regardless of when during `draw()` it was called.
+    /// - Canvases record their render passes when they are finished, not when `draw()` is
+    ///   called on them.
     #[inline]
     pub fn wgpu(&self) -> &WgpuContext {
         &self.wgpu
@@ -520,11 +574,75 @@ impl GraphicsContext {
     }
 
     /// Returns the size of the window’s underlying drawable in physical pixels as (width, height).
+    ///
+    /// This is the size of the framebuffer that is rendered to, and the size passed to
+    /// [`resize_event()`](../event/trait.EventHandler.html#method.resize_event).
+    /// On HiDPI displays it is larger than the [`logical_size()`](#method.logical_size).
     pub fn drawable_size(&self) -> (f32, f32) {
         let size = self.window.inner_size();
         (size.width as f32, size.height as f32)
     }
 
+    /// Returns the size of the window’s drawable area in logical pixels as (width, height),
+    /// that is the [`drawable_size()`](#method.drawable_size) divided by the
+    /// [`scale_factor()`](#method.scale_factor).
+    pub fn logical_size(&self) -> (f32, f32) {
+        let size = self
+            .window
+            .inner_size()
+            .to_logical::<f32>(self.window.scale_factor());
+        (size.width, size.height)
+    }
+
+    /// Returns the ratio of physical to logical pixels of the monitor the window is on,
+    /// e.g. `2.0` on a typical HiDPI display.
+    pub fn scale_factor(&self) -> f64 {
+        self.window.scale_factor()
+    }
+
+    /// Returns how much GPU memory is used by all buffers and textures that are alive,
+    /// to help track down running out of video memory.
+    ///
+    /// Sizes are computed from the dimensions and formats of the resources, so the actual
+    /// memory used by the driver can be somewhat higher due to padding and alignment.
+    pub fn memory_stats(&self) -> GpuMemoryStats {
+        let ((buffer_count, buffer_bytes), (texture_count, texture_bytes)) =
+            super::gpu::arc::memory_usage();
+        GpuMemoryStats {
+            texture_count,
+            texture_bytes,
+            buffer_count,
+            buffer_bytes,
+        }
+    }
+
+    /// Returns information about the graphics adapter in use, like its name, backend
+    /// (Vulkan, DX12, Metal, GL...) and whether it is an integrated or discrete GPU.
+    ///
+    /// Worth including in bug reports about performance or rendering issues.
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Writes the window and adapter section of [`Context::debug_dump()`](crate::Context::debug_dump).
+    pub(crate) fn write_debug_dump(&self, out: &mut impl std::fmt::Write) -> std::fmt::Result {
+        let (width, height) = self.drawable_size();
+        writeln!(out, "[graphics]")?;
+        writeln!(out, "  drawable size: {width}x{height}")?;
+        writeln!(out, "  scale factor: {}", self.scale_factor())?;
+        writeln!(
+            out,
+            "  adapter: {} ({:?}, {:?})",
+            self.adapter_info.name, self.adapter_info.backend, self.adapter_info.device_type
+        )?;
+        writeln!(out, "  surface format: {:?}", self.surface_config.format)?;
+        writeln!(
+            out,
+            "  present mode: {:?}",
+            self.surface_config.present_mode
+        )
+    }
+
     /// Sets the window size (in physical pixels) / resolution to the specified width and height.
     ///
     /// Note:   These dimensions are only interpreted as resolutions in true fullscreen mode.
@@ -534,6 +652,15 @@ impl GraphicsContext {
     }
 
     /// Sets the window title.
+    ///
+    /// This can be called at any time, for example to show the score or the FPS in the title bar:
+    ///
+    /// ```rust,no_run
+    /// # fn t(ctx: &mut ggez::Context) {
+    /// let fps = ctx.time.fps();
+    /// ctx.gfx.set_window_title(&format!("My Game - {fps:.0} FPS"));
+    /// # }
+    /// ```
     pub fn set_window_title(&self, title: &str) {
         self.window.set_title(title);
     }
@@ -568,7 +695,47 @@ impl GraphicsContext {
             .map(|vm| vm.size())
     }
 
+    /// Returns all video modes supported by the monitor the window is currently on,
+    /// for use with [`set_exclusive_fullscreen()`](#method.set_exclusive_fullscreen).
+    ///
+    /// Returns an empty list if the monitor can't be determined.
+    pub fn video_modes(&self) -> Vec<winit::monitor::VideoMode> {
+        self.window
+            .current_monitor()
+            .map(|monitor| monitor.video_modes().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the refresh rate of the monitor the window is currently on, in Hz.
+    ///
+    /// If the monitor doesn't report a single current rate (as is the case with some
+    /// variable refresh rate displays), the highest rate among its video modes is returned.
+    /// Returns `None` if the monitor can't be determined.
+    pub fn current_monitor_refresh_rate(&self) -> Option<f32> {
+        let monitor = self.window.current_monitor()?;
+        monitor
+            .refresh_rate_millihertz()
+            .or_else(|| {
+                monitor
+                    .video_modes()
+                    .map(|vm| vm.refresh_rate_millihertz())
+                    .max()
+            })
+            .map(|millihertz| millihertz as f32 / 1000.0)
+    }
+
     /// Returns a reference to the Winit window.
+    ///
+    /// This is an escape hatch for window features that `ggez` doesn't wrap,
+    /// or for interop with platform APIs through the raw window handle.
+    /// Keep in mind that `ggez` tracks some window state of its own (such as
+    /// the [`WindowMode`](../conf/struct.WindowMode.html) or the cursor state in
+    /// [`MouseContext`](../input/mouse/struct.MouseContext.html)); changing those
+    /// properties directly on the window will not be reflected there.
+    ///
+    /// The `winit` version used here is not covered by `ggez`'s semver guarantees
+    /// and may change in any minor release. Use the
+    /// [`ggez::winit`](../context/winit/index.html) re-export to stay in sync.
     #[inline]
     pub fn window(&self) -> &winit::window::Window {
         &self.window
@@ -595,10 +762,162 @@ impl GraphicsContext {
         self.set_mode(window_mode)
     }
 
+    /// Switches the window to exclusive fullscreen using the given video mode,
+    /// which selects the resolution, bit depth and refresh rate.
+    ///
+    /// Use [`video_modes()`](#method.video_modes) to find the modes the monitor supports.
+    /// [`set_fullscreen(FullscreenType::True)`](#method.set_fullscreen) on the other hand
+    /// just picks the first mode matching the current window size.
+    pub fn set_exclusive_fullscreen(&mut self, mode: winit::monitor::VideoMode) -> GameResult {
+        let size = mode.size();
+        self.window
+            .set_fullscreen(Some(winit::window::Fullscreen::Exclusive(mode)));
+        self.window_mode = self
+            .window_mode
+            .fullscreen_type(FullscreenType::True)
+            .dimensions(size.width as f32, size.height as f32);
+
+        self.surface_config.width = size.width.max(1);
+        self.surface_config.height = size.height.max(1);
+        self.wgpu
+            .surface
+            .configure(&self.wgpu.device, &self.surface_config);
+        Ok(())
+    }
+
     /// Sets whether or not the window is resizable.
+    ///
+    /// Unlike [`set_mode()`](#method.set_mode) this leaves the current window size untouched.
     pub fn set_resizable(&mut self, resizable: bool) -> GameResult {
-        let window_mode = self.window_mode.resizable(resizable);
-        self.set_mode(window_mode)
+        self.window_mode = self.window_mode.resizable(resizable);
+        self.window.set_resizable(resizable);
+        Ok(())
+    }
+
+    /// Sets whether or not the window is transparent.
+    ///
+    /// The surface is reconfigured to blend with whatever is behind the window, which
+    /// fails if the surface doesn't support an alpha-capable mode. Note that `winit` can't
+    /// change the transparency of an existing window on Windows and X11, so there you
+    /// should set [`WindowMode::transparent`](../conf/struct.WindowMode.html#structfield.transparent)
+    /// when creating the context instead.
+    pub fn set_window_transparent(&mut self, transparent: bool) -> GameResult {
+        let alpha_mode = surface_alpha_mode(&self.alpha_modes, transparent).ok_or_else(|| {
+            GameError::WindowError("The window surface doesn't support transparency".to_string())
+        })?;
+        self.window_mode = self.window_mode.transparent(transparent);
+        self.window.set_transparent(transparent);
+        self.surface_config.alpha_mode = alpha_mode;
+        self.wgpu
+            .surface
+            .configure(&self.wgpu.device, &self.surface_config);
+        Ok(())
+    }
+
+    /// Starts moving the window with the mouse, as if its title bar was dragged.
+    /// Call this while the left mouse button is pressed.
+    ///
+    /// See [`EventHandler::window_hit_test()`](../event/trait.EventHandler.html#method.window_hit_test)
+    /// for the easier way to add a custom title bar.
+    pub fn drag_window(&self) -> GameResult {
+        self.window
+            .drag_window()
+            .map_err(|e| GameError::WindowError(format!("Couldn't drag the window: {e}")))
+    }
+
+    /// Starts resizing the window with the mouse, as if the given border was dragged.
+    /// Call this while the left mouse button is pressed. Only supported on X11 by `winit`.
+    pub fn drag_resize_window(&self, direction: winit::window::ResizeDirection) -> GameResult {
+        self.window
+            .drag_resize_window(direction)
+            .map_err(|e| GameError::WindowError(format!("Couldn't resize the window: {e}")))
+    }
+
+    /// Sets whether the window receives mouse input at all. If `false`, clicks pass through
+    /// the window to whatever is behind it, e.g. for a transparent overlay.
+    pub fn set_cursor_hittest(&self, hittest: bool) -> GameResult {
+        self.window
+            .set_cursor_hittest(hittest)
+            .map_err(|e| GameError::WindowError(format!("Couldn't set cursor hit test: {e}")))
+    }
+
+    /// Sets whether or not the window is kept above all other windows.
+    pub fn set_always_on_top(&self, always_on_top: bool) {
+        self.window.set_window_level(if always_on_top {
+            winit::window::WindowLevel::AlwaysOnTop
+        } else {
+            winit::window::WindowLevel::Normal
+        });
+    }
+
+    /// Minimizes the window, or restores it from being minimized.
+    ///
+    /// When restoring, the window is also asked to take focus, which
+    /// some platforms may refuse.
+    pub fn set_minimized(&self, minimized: bool) {
+        self.window.set_minimized(minimized);
+        if !minimized {
+            self.window.focus_window();
+        }
+    }
+
+    /// Returns whether the window currently uses a light or dark theme,
+    /// or `None` if the platform doesn't say.
+    pub fn current_theme(&self) -> Option<winit::window::Theme> {
+        self.window.theme()
+    }
+
+    /// Asks the window system for a redraw, waking up the event loop to run a new frame.
+    ///
+    /// This is only needed with [`LoopMode::Wait`](crate::event::LoopMode::Wait) or
+    /// [`LoopMode::WaitFor`](crate::event::LoopMode::WaitFor), e.g. when an asset
+    /// finished loading in the background and should be shown.
+    pub fn request_redraw(&self) {
+        self.window.request_redraw();
+    }
+
+    /// Maximizes the window, or restores it from being maximized.
+    pub fn set_maximized(&self, maximized: bool) {
+        self.window.set_maximized(maximized);
+    }
+
+    /// Returns whether or not the window is currently maximized.
+    pub fn is_maximized(&self) -> bool {
+        self.window.is_maximized()
+    }
+
+    /// Sets the minimum size of the window's drawable area, in physical pixels.
+    ///
+    /// Both dimensions need to be at least 1.
+    pub fn set_min_size(&mut self, width: f32, height: f32) -> GameResult {
+        if width < 1.0 || height < 1.0 {
+            return Err(GameError::WindowError(format!(
+                "window min_width and min_height need to be at least 1; actual values: {width}, {height}"
+            )));
+        }
+        self.window_mode.min_width = width;
+        self.window_mode.min_height = height;
+        self.window.set_min_inner_size(Some(dpi::PhysicalSize {
+            width: f64::from(width),
+            height: f64::from(height),
+        }));
+        Ok(())
+    }
+
+    /// Sets the maximum size of the window's drawable area, in physical pixels.
+    ///
+    /// A width or height of 0 removes the limit.
+    pub fn set_max_size(&mut self, width: f32, height: f32) {
+        self.window_mode = self.window_mode.max_dimensions(width, height);
+        let max_dimensions = if width > 0.0 && height > 0.0 {
+            Some(dpi::PhysicalSize {
+                width: f64::from(width),
+                height: f64::from(height),
+            })
+        } else {
+            None
+        };
+        self.window.set_max_inner_size(max_dimensions);
     }
 
     /// Sets the window mode, such as the size and other properties.
@@ -628,13 +947,59 @@ impl GraphicsContext {
         self.frame_image.as_ref().unwrap(/* invariant */)
     }
 
+    /// Reads back the contents of the [frame image](#method.frame) as 8-bit RGBA pixels,
+    /// row by row from the top left.
+    ///
+    /// With `linearize` set to `false`, the pixels are the sRGB values you see on screen,
+    /// which is what image files expect. With `true`, they are converted to linear
+    /// color values instead, like the ones shaders work with. Either way the pixels are
+    /// reordered to RGBA if the surface uses BGRA.
+    ///
+    /// The frame holds what was drawn in the last completed frame, so call this outside of
+    /// `draw()`, e.g. in `update()`. **This is a very expensive operation - call sparingly.**
+    pub fn take_screenshot(&self, linearize: bool) -> GameResult<Vec<u8>> {
+        let frame = self.frame();
+        let pixels = frame.to_pixels(self)?;
+        super::image::to_rgba8(pixels, frame.format(), linearize)
+    }
+
     /// Returns the image format of the window surface.
     #[inline]
     pub fn surface_format(&self) -> ImageFormat {
         self.surface_config.format
     }
 
+    /// Returns the image formats the window surface supports, the preferred one first.
+    ///
+    /// Pick one of these with
+    /// [`ContextBuilder::surface_format()`](../struct.ContextBuilder.html#method.surface_format),
+    /// e.g. `Rgba16Float` for HDR or `Rgb10a2Unorm` for wide-gamut output.
+    pub fn supported_formats(&self) -> &[ImageFormat] {
+        &self.surface_formats
+    }
+
+    /// Reconfigures the surface to use `format`, recreating the frame images to match.
+    pub(crate) fn set_surface_format(&mut self, format: ImageFormat) -> GameResult {
+        if !self.surface_formats.contains(&format) {
+            return Err(GameError::WindowError(format!(
+                "The window surface doesn't support the {format:?} format"
+            )));
+        }
+        self.surface_config.format = format;
+        self.wgpu
+            .surface
+            .configure(&self.wgpu.device, &self.surface_config);
+
+        let samples = self.frame_msaa_image.as_ref().map_or(1, Image::samples);
+        self.frame = Some(ScreenImage::new(self, None, 1., 1., 1));
+        self.frame_msaa = Some(ScreenImage::new(self, None, 1., 1., samples));
+        self.update_frame_image();
+        Ok(())
+    }
+
     /// Returns the current [`wgpu::CommandEncoder`] if there is a frame in progress.
+    ///
+    /// See [`wgpu()`](#method.wgpu) for how commands encoded here are ordered.
     pub fn commands(&mut self) -> Option<&mut wgpu::CommandEncoder> {
         self.fcx.as_mut().map(|fcx| &mut fcx.cmd)
     }
@@ -642,6 +1007,9 @@ impl GraphicsContext {
     /// Begins a new frame.
     ///
     /// The only situation you need to call this in is when you are rolling your own event loop.
+    /// If the surface is lost or outdated it is reconfigured and acquired again once; if that
+    /// still fails, or acquiring the frame times out, a [`GameError::SurfaceError`] is returned
+    /// and the frame should just be skipped.
     pub fn begin_frame(&mut self) -> GameResult {
         if self.fcx.is_some() {
             return Err(GameError::RenderError(String::from(
@@ -649,20 +1017,20 @@ impl GraphicsContext {
             )));
         }
 
-        let size = self.window.inner_size();
         let frame = match self.wgpu.surface.get_current_texture() {
-            Ok(frame) => Ok(frame),
-            Err(_) => {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                // the surface needs to be reconfigured, e.g. after a resize or a monitor change
+                let size = self.window.inner_size();
                 self.surface_config.width = size.width.max(1);
                 self.surface_config.height = size.height.max(1);
                 self.wgpu
                     .surface
                     .configure(&self.wgpu.device, &self.surface_config);
-                self.wgpu.surface.get_current_texture().map_err(|_| {
-                    GameError::RenderError(String::from("failed to get next swapchain image"))
-                })
+                self.wgpu.surface.get_current_texture()?
             }
-        }?;
+            Err(e) => return Err(e.into()),
+        };
 
         let frame_view = frame
             .texture
@@ -752,6 +1120,23 @@ impl GraphicsContext {
         }
     }
 
+    /// Begins a new frame and returns a guard that ends it when dropped.
+    ///
+    /// This is a safer alternative to pairing [`begin_frame()`](#method.begin_frame)
+    /// and [`end_frame()`](#method.end_frame) by hand in your own event loop.
+    /// The guard can be passed to anything that takes a `GraphicsContext`, such as
+    /// [`Canvas::from_frame()`](struct.Canvas.html#method.from_frame).
+    ///
+    /// Dropping the guard can only log errors from `end_frame()`;
+    /// call [`FrameGuard::finish()`](struct.FrameGuard.html#method.finish) to get them instead.
+    pub fn frame_guard(&mut self) -> GameResult<FrameGuard<'_>> {
+        self.begin_frame()?;
+        Ok(FrameGuard {
+            gfx: self,
+            finished: false,
+        })
+    }
+
     pub(crate) fn resize(&mut self, _new_size: dpi::PhysicalSize<u32>) {
         let size = self.window.inner_size();
         let _ = self.wgpu.device.poll(wgpu::Maintain::Wait);
@@ -858,6 +1243,68 @@ impl GraphicsContext {
     }
 }
 
+/// A frame in progress, as returned by
+/// [`GraphicsContext::frame_guard()`](struct.GraphicsContext.html#method.frame_guard).
+///
+/// Ends the frame when dropped.
+pub struct FrameGuard<'a> {
+    gfx: &'a mut GraphicsContext,
+    finished: bool,
+}
+
+impl<'a> FrameGuard<'a> {
+    /// Ends the frame, returning any error from
+    /// [`GraphicsContext::end_frame()`](struct.GraphicsContext.html#method.end_frame).
+    pub fn finish(mut self) -> GameResult {
+        self.finished = true;
+        self.gfx.end_frame()
+    }
+}
+
+impl<'a> Drop for FrameGuard<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(e) = self.gfx.end_frame() {
+                error!("Error on GraphicsContext::end_frame(): {e:?}");
+            }
+        }
+    }
+}
+
+impl<'a> std::fmt::Debug for FrameGuard<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<FrameGuard: {self:p}>")
+    }
+}
+
+impl<'a> std::ops::Deref for FrameGuard<'a> {
+    type Target = GraphicsContext;
+
+    fn deref(&self) -> &GraphicsContext {
+        self.gfx
+    }
+}
+
+impl<'a> std::ops::DerefMut for FrameGuard<'a> {
+    fn deref_mut(&mut self) -> &mut GraphicsContext {
+        self.gfx
+    }
+}
+
+impl<'a> Has<GraphicsContext> for FrameGuard<'a> {
+    #[inline]
+    fn retrieve(&self) -> &GraphicsContext {
+        self.gfx
+    }
+}
+
+impl<'a> HasMut<GraphicsContext> for FrameGuard<'a> {
+    #[inline]
+    fn retrieve_mut(&mut self) -> &mut GraphicsContext {
+        self.gfx
+    }
+}
+
 // This is kinda awful 'cause it copies a couple times,
 // but still better than
 // having `winit` try to do the image loading for us.
@@ -879,3 +1326,17 @@ pub(crate) fn load_icon(
         GameError::ResourceLoadError(msg)
     })
 }
+
+/// Picks the surface alpha mode for a transparent or opaque window out of the supported ones.
+fn surface_alpha_mode(
+    supported: &[wgpu::CompositeAlphaMode],
+    transparent: bool,
+) -> Option<wgpu::CompositeAlphaMode> {
+    use wgpu::CompositeAlphaMode as Mode;
+    if !transparent {
+        return Some(Mode::Auto);
+    }
+    [Mode::PreMultiplied, Mode::PostMultiplied, Mode::Inherit]
+        .into_iter()
+        .find(|mode| supported.contains(mode))
+}
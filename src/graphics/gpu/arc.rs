@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::sync::{
     atomic::{AtomicU64, Ordering::SeqCst},
     Arc,
@@ -5,6 +6,90 @@ use std::sync::{
 
 static NEXT_ID: AtomicU64 = AtomicU64::new(0);
 
+static BUFFERS: ResourceCounter = ResourceCounter::new();
+static TEXTURES: ResourceCounter = ResourceCounter::new();
+
+/// Running totals of the live GPU resources of one kind.
+struct ResourceCounter {
+    count: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl ResourceCounter {
+    const fn new() -> Self {
+        ResourceCounter {
+            count: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    fn get(&self) -> (u64, u64) {
+        (self.count.load(SeqCst), self.bytes.load(SeqCst))
+    }
+}
+
+/// Counts a buffer or texture towards its `ResourceCounter` until the last clone of its
+/// `ArcHandle` is dropped.
+#[derive(Debug)]
+struct MemoryToken {
+    counter: &'static ResourceCounter,
+    bytes: u64,
+}
+
+impl MemoryToken {
+    fn for_resource(resource: &dyn Any) -> Option<Self> {
+        let (counter, bytes) = if let Some(buffer) = resource.downcast_ref::<wgpu::Buffer>() {
+            (&BUFFERS, buffer.size())
+        } else if let Some(texture) = resource.downcast_ref::<wgpu::Texture>() {
+            (&TEXTURES, texture_bytes(texture))
+        } else {
+            return None;
+        };
+        let _ = counter.count.fetch_add(1, SeqCst);
+        let _ = counter.bytes.fetch_add(bytes, SeqCst);
+        Some(MemoryToken { counter, bytes })
+    }
+}
+
+impl Drop for MemoryToken {
+    fn drop(&mut self) {
+        let _ = self.counter.count.fetch_sub(1, SeqCst);
+        let _ = self.counter.bytes.fetch_sub(self.bytes, SeqCst);
+    }
+}
+
+impl std::fmt::Debug for ResourceCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<ResourceCounter: {:?}>", self.get())
+    }
+}
+
+/// Estimates the memory used by a texture, including all of its mip levels and samples.
+fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = u64::from(format.block_size(None).unwrap_or(4));
+    let size = texture.size();
+    (0..texture.mip_level_count())
+        .map(|level| {
+            let width = (size.width >> level).max(1);
+            let height = (size.height >> level).max(1);
+            let blocks_wide = (width + block_width - 1) / block_width;
+            let blocks_high = (height + block_height - 1) / block_height;
+            u64::from(blocks_wide) * u64::from(blocks_high)
+        })
+        .sum::<u64>()
+        * block_size
+        * u64::from(size.depth_or_array_layers)
+        * u64::from(texture.sample_count())
+}
+
+/// Returns the number and total size in bytes of the live buffers and textures,
+/// as `((buffer count, buffer bytes), (texture count, texture bytes))`.
+pub fn memory_usage() -> ((u64, u64), (u64, u64)) {
+    (BUFFERS.get(), TEXTURES.get())
+}
+
 /// Arc'd WGPU handles are used widely across the graphics module.
 ///
 /// Beyond allowing for Clone, they also allow different GPU resources to be
@@ -13,11 +98,13 @@ static NEXT_ID: AtomicU64 = AtomicU64::new(0);
 pub struct ArcHandle<T: 'static> {
     pub handle: Arc<T>,
     id: u64,
+    memory: Option<Arc<MemoryToken>>,
 }
 
 impl<T: 'static> ArcHandle<T> {
     pub fn new(handle: T) -> Self {
         ArcHandle {
+            memory: MemoryToken::for_resource(&handle).map(Arc::new),
             handle: Arc::new(handle),
             id: NEXT_ID.fetch_add(1, SeqCst),
         }
@@ -34,6 +121,7 @@ impl<T: 'static> Clone for ArcHandle<T> {
         ArcHandle {
             handle: Arc::clone(&self.handle),
             id: self.id,
+            memory: self.memory.clone(),
         }
     }
 }
@@ -458,3 +458,67 @@ impl ScreenImage {
         Image::new_canvas_image(gfx, format, width, height, samples)
     }
 }
+
+/// Converts pixels read back from an 8-bit RGBA or BGRA image to RGBA, optionally
+/// turning sRGB encoded colors into linear ones. Alpha is always linear.
+pub(crate) fn to_rgba8(
+    mut pixels: Vec<u8>,
+    format: ImageFormat,
+    linearize: bool,
+) -> GameResult<Vec<u8>> {
+    let bgra = match format {
+        ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => false,
+        ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => true,
+        format => {
+            return Err(GameError::RenderError(format!(
+                "cannot convert pixels of the {format:#?} GPU image format to RGBA"
+            )))
+        }
+    };
+    let linearize = linearize && format.is_srgb();
+    for pixel in pixels.chunks_exact_mut(4) {
+        if bgra {
+            pixel.swap(0, 2);
+        }
+        if linearize {
+            for channel in &mut pixel[..3] {
+                *channel = srgb_to_linear(*channel);
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+fn srgb_to_linear(value: u8) -> u8 {
+    let c = f32::from(value) / 255.0;
+    let linear = if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    };
+    (linear * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba8_conversion() {
+        let pixels = vec![10, 20, 188, 255];
+        assert_eq!(
+            to_rgba8(pixels.clone(), ImageFormat::Bgra8UnormSrgb, false).unwrap(),
+            vec![188, 20, 10, 255]
+        );
+        assert_eq!(
+            to_rgba8(pixels.clone(), ImageFormat::Rgba8UnormSrgb, true).unwrap(),
+            vec![1, 2, 128, 255]
+        );
+        // linear formats are already linear
+        assert_eq!(
+            to_rgba8(pixels.clone(), ImageFormat::Rgba8Unorm, true).unwrap(),
+            pixels
+        );
+        assert!(to_rgba8(pixels, ImageFormat::R8Unorm, false).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Action bindings; map the actions of your game to keyboard, mouse and gamepad inputs.
+//!
+//! Instead of checking for specific keys all over your game logic, you can
+//! define an action type and bind any number of inputs to each action.
+//! This makes it easy to support several input devices at once, and to
+//! let players remap their controls.
+//!
+//! -----
+//!
+//! Example:
+//!
+//! ```rust, compile
+//! use ggez::input::bindings::InputMap;
+//! use ggez::input::keyboard::KeyCode;
+//! use ggez::input::mouse::MouseButton;
+//! use ggez::{Context, GameResult};
+//!
+//! #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+//! enum Action {
+//!     Jump,
+//!     Shoot,
+//! }
+//!
+//! fn controls() -> InputMap<Action> {
+//!     let mut controls = InputMap::new();
+//!     controls.bind(Action::Jump, KeyCode::Space);
+//!     controls.bind(Action::Jump, KeyCode::Up);
+//!     controls.bind(Action::Shoot, MouseButton::Left);
+//!     controls
+//! }
+//!
+//! fn update(ctx: &mut Context, controls: &InputMap<Action>) -> GameResult {
+//!     if controls.is_action_pressed(ctx, &Action::Jump) {
+//!         println!("Jumping!");
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+
+use crate::context::Context;
+use crate::error::GameResult;
+use crate::input::keyboard::{KeyCode, ScanCode};
+use crate::input::mouse::MouseButton;
+
+/// A physical input that can be bound to an action.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", content = "input")]
+pub enum Binding {
+    /// A keyboard key, identified by its key code.
+    Key(KeyCode),
+    /// A keyboard key, identified by its scan code.
+    /// See the [`keyboard`](../keyboard/index.html) module for when to prefer these.
+    ScanCode(ScanCode),
+    /// A mouse button.
+    MouseButton(#[serde(with = "mouse_button")] MouseButton),
+    /// A button on any of the connected gamepads.
+    #[cfg(feature = "gamepad")]
+    GamepadButton(gilrs::Button),
+}
+
+impl Binding {
+    /// Checks if the input is currently pressed down.
+    pub fn is_pressed(&self, ctx: &Context) -> bool {
+        match *self {
+            Binding::Key(key) => ctx.keyboard.is_key_pressed(key),
+            Binding::ScanCode(code) => ctx.keyboard.is_scancode_pressed(code),
+            Binding::MouseButton(button) => ctx.mouse.button_pressed(button),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadButton(button) => ctx
+                .gamepad
+                .gamepads()
+                .any(|(_id, gamepad)| gamepad.is_pressed(button)),
+        }
+    }
+}
+
+impl From<KeyCode> for Binding {
+    fn from(key: KeyCode) -> Self {
+        Binding::Key(key)
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(button: MouseButton) -> Self {
+        Binding::MouseButton(button)
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl From<gilrs::Button> for Binding {
+    fn from(button: gilrs::Button) -> Self {
+        Binding::GamepadButton(button)
+    }
+}
+
+/// Maps the actions of your game to the inputs that trigger them.
+///
+/// `A` is your own action type, usually a simple fieldless enum.
+///
+/// If `A` implements `serde`'s traits, so does the `InputMap`, which lets you
+/// save and load control schemes, for example with
+/// [`to_toml_file()`](#method.to_toml_file) and [`Filesystem::create()`](../../filesystem/struct.Filesystem.html#method.create).
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "A: serde::Serialize",
+    deserialize = "A: Eq + Hash + serde::Deserialize<'de>"
+))]
+pub struct InputMap<A> {
+    #[serde(with = "entries")]
+    bindings: HashMap<A, Vec<Binding>>,
+}
+
+/// (De)serializes mouse buttons as their name, or their number for `MouseButton::Other`,
+/// since TOML can't represent enum variants with fields.
+mod mouse_button {
+    use super::MouseButton;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Name(String),
+        Other(u16),
+    }
+
+    pub fn serialize<S>(button: &MouseButton, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let repr = match *button {
+            MouseButton::Left => Repr::Name("Left".to_owned()),
+            MouseButton::Right => Repr::Name("Right".to_owned()),
+            MouseButton::Middle => Repr::Name("Middle".to_owned()),
+            MouseButton::Other(n) => Repr::Other(n),
+        };
+        repr.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<MouseButton, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Repr::deserialize(deserializer)? {
+            Repr::Name(name) => match name.as_str() {
+                "Left" => Ok(MouseButton::Left),
+                "Right" => Ok(MouseButton::Right),
+                "Middle" => Ok(MouseButton::Middle),
+                _ => Err(de::Error::unknown_variant(
+                    &name,
+                    &["Left", "Right", "Middle"],
+                )),
+            },
+            Repr::Other(n) => Ok(MouseButton::Other(n)),
+        }
+    }
+}
+
+/// (De)serializes the bindings as a list of entries rather than a map,
+/// since formats such as TOML only allow strings as map keys.
+mod entries {
+    use super::Binding;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    #[derive(Serialize)]
+    struct EntryRef<'a, A> {
+        action: &'a A,
+        inputs: &'a [Binding],
+    }
+
+    #[derive(Deserialize)]
+    struct Entry<A> {
+        action: A,
+        inputs: Vec<Binding>,
+    }
+
+    pub fn serialize<S, A>(map: &HashMap<A, Vec<Binding>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        A: Serialize,
+    {
+        serializer.collect_seq(
+            map.iter()
+                .map(|(action, inputs)| EntryRef { action, inputs }),
+        )
+    }
+
+    pub fn deserialize<'de, D, A>(deserializer: D) -> Result<HashMap<A, Vec<Binding>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        A: Eq + Hash + Deserialize<'de>,
+    {
+        let entries = Vec::<Entry<A>>::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| (entry.action, entry.inputs))
+            .collect())
+    }
+}
+
+impl<A> InputMap<A>
+where
+    A: Eq + Hash,
+{
+    /// Creates a new `InputMap` without any bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Binds an input to an action.
+    /// An action can have any number of bindings; binding the same input twice does nothing.
+    pub fn bind(&mut self, action: A, binding: impl Into<Binding>) {
+        let binding = binding.into();
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes an input from an action.
+    ///
+    /// Returns whether or not the input was bound to the action.
+    pub fn unbind(&mut self, action: &A, binding: impl Into<Binding>) -> bool {
+        let binding = binding.into();
+        if let Some(bindings) = self.bindings.get_mut(action) {
+            let len = bindings.len();
+            bindings.retain(|b| *b != binding);
+            let removed = bindings.len() != len;
+            if bindings.is_empty() {
+                let _ = self.bindings.remove(action);
+            }
+            removed
+        } else {
+            false
+        }
+    }
+
+    /// Removes all inputs from an action.
+    pub fn clear_action(&mut self, action: &A) {
+        let _ = self.bindings.remove(action);
+    }
+
+    /// Returns the inputs bound to an action.
+    pub fn bindings(&self, action: &A) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Checks if any of the inputs bound to an action is currently pressed down.
+    pub fn is_action_pressed(&self, ctx: &Context, action: &A) -> bool {
+        self.bindings(action).iter().any(|b| b.is_pressed(ctx))
+    }
+
+    /// Returns all pairs of different actions which share at least one input.
+    ///
+    /// Each pair is only listed once, in no particular order.
+    pub fn conflicts(&self) -> Vec<(A, A)>
+    where
+        A: Clone,
+    {
+        let actions: Vec<_> = self.bindings.iter().collect();
+        let mut conflicts = Vec::new();
+        for (i, (action, bindings)) in actions.iter().enumerate() {
+            for (other_action, other_bindings) in &actions[i + 1..] {
+                if bindings.iter().any(|b| other_bindings.contains(b)) {
+                    conflicts.push(((*action).clone(), (*other_action).clone()));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Load a TOML file from the given `Read` and attempts to parse
+    /// an `InputMap` from it.
+    pub fn from_toml_file<R: io::Read>(file: &mut R) -> GameResult<Self>
+    where
+        A: serde::de::DeserializeOwned,
+    {
+        let mut s = String::new();
+        let _ = file.read_to_string(&mut s)?;
+        let decoded = toml::from_str(&s)?;
+        Ok(decoded)
+    }
+
+    /// Saves the `InputMap` to the given `Write` object,
+    /// formatted as TOML.
+    pub fn to_toml_file<W: io::Write>(&self, file: &mut W) -> GameResult
+    where
+        A: serde::Serialize,
+    {
+        let s = toml::to_vec(self)?;
+        file.write_all(&s)?;
+        Ok(())
+    }
+}
+
+impl<A> Default for InputMap<A>
+where
+    A: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    enum Action {
+        Jump,
+        Shoot,
+        Crouch,
+    }
+
+    #[test]
+    fn binding_and_unbinding() {
+        let mut map = InputMap::new();
+        assert!(map.bindings(&Action::Jump).is_empty());
+        map.bind(Action::Jump, KeyCode::Space);
+        map.bind(Action::Jump, KeyCode::Space);
+        map.bind(Action::Jump, Binding::ScanCode(57));
+        map.bind(Action::Shoot, MouseButton::Left);
+        assert_eq!(
+            map.bindings(&Action::Jump),
+            &[Binding::Key(KeyCode::Space), Binding::ScanCode(57)]
+        );
+        assert_eq!(
+            map.bindings(&Action::Shoot),
+            &[Binding::MouseButton(MouseButton::Left)]
+        );
+        assert!(map.unbind(&Action::Jump, KeyCode::Space));
+        assert!(!map.unbind(&Action::Jump, KeyCode::Space));
+        assert_eq!(map.bindings(&Action::Jump), &[Binding::ScanCode(57)]);
+        map.clear_action(&Action::Jump);
+        assert!(map.bindings(&Action::Jump).is_empty());
+    }
+
+    #[test]
+    fn conflicting_bindings() {
+        let mut map = InputMap::new();
+        map.bind(Action::Jump, KeyCode::Space);
+        map.bind(Action::Shoot, MouseButton::Left);
+        map.bind(Action::Crouch, KeyCode::C);
+        assert!(map.conflicts().is_empty());
+        map.bind(Action::Shoot, KeyCode::Space);
+        let conflicts = map.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert!(
+            conflicts[0] == (Action::Jump, Action::Shoot)
+                || conflicts[0] == (Action::Shoot, Action::Jump)
+        );
+    }
+
+    /// Tries to encode and decode an `InputMap`
+    /// and makes sure it gets the same bindings back.
+    #[test]
+    fn headless_encode_round_trip() {
+        let mut map1 = InputMap::new();
+        map1.bind(Action::Jump, KeyCode::Space);
+        map1.bind(Action::Jump, Binding::ScanCode(57));
+        map1.bind(Action::Shoot, MouseButton::Left);
+        map1.bind(Action::Crouch, MouseButton::Other(8));
+        let mut writer = Vec::new();
+        map1.to_toml_file(&mut writer).unwrap();
+        let mut reader = writer.as_slice();
+        let map2 = InputMap::<Action>::from_toml_file(&mut reader).unwrap();
+        for action in [Action::Jump, Action::Shoot, Action::Crouch] {
+            assert_eq!(map1.bindings(&action), map2.bindings(&action));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+use crate::context::Context;
+
+/// Filters out inputs that repeat faster than a given interval, such as a double-detected
+/// click or a bouncing gamepad button.
+///
+/// The ids can be anything that identifies an input source, e.g. a
+/// [`KeyCode`](../keyboard/enum.KeyCode.html) or one of your game's actions, and each id
+/// has its own cooldown. Time is measured with
+/// [`TimeContext::time_since_start()`](../../timer/struct.TimeContext.html#method.time_since_start).
+///
+/// ```rust
+/// # use ggez::input::Debouncer;
+/// # use ggez::input::mouse::MouseButton;
+/// # use std::time::Duration;
+/// # fn t(ctx: &ggez::Context) {
+/// let mut clicks = Debouncer::new(Duration::from_millis(150));
+/// // in mouse_button_down_event()
+/// if clicks.trigger(ctx, MouseButton::Left) {
+///     println!("Click!");
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Debouncer<T> {
+    interval: Duration,
+    last: HashMap<T, Duration>,
+}
+
+impl<T: Eq + Hash> Debouncer<T> {
+    /// Creates a new `Debouncer` that lets every id through at most once per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Debouncer {
+            interval,
+            last: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if the input `id` should be acted upon, that is if it wasn't
+    /// triggered during the last interval. Inputs that are filtered out don't restart
+    /// the cooldown.
+    pub fn trigger(&mut self, ctx: &Context, id: T) -> bool {
+        self.trigger_at(id, ctx.time.time_since_start())
+    }
+
+    fn trigger_at(&mut self, id: T, now: Duration) -> bool {
+        match self.last.get(&id) {
+            Some(&last) if now.saturating_sub(last) < self.interval => false,
+            _ => {
+                let _ = self.last.insert(id, now);
+                true
+            }
+        }
+    }
+
+    /// Ends the cooldown of `id`, so it will trigger again right away.
+    pub fn reset(&mut self, id: &T) {
+        let _ = self.last.remove(id);
+    }
+
+    /// Ends the cooldowns of all ids.
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+
+    /// Returns the interval inputs are filtered by.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Sets the interval inputs are filtered by.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triggers_once_per_interval() {
+        let ms = Duration::from_millis;
+        let mut debouncer = Debouncer::new(ms(100));
+        assert!(debouncer.trigger_at('a', ms(0)));
+        assert!(!debouncer.trigger_at('a', ms(50)));
+        assert!(debouncer.trigger_at('b', ms(60)));
+        assert!(debouncer.trigger_at('a', ms(100)));
+        assert!(!debouncer.trigger_at('a', ms(150)));
+        debouncer.reset(&'a');
+        assert!(debouncer.trigger_at('a', ms(160)));
+    }
+}
@@ -0,0 +1,158 @@
+//! A per-frame queue of input events, for processing input as data instead of through callbacks.
+
+use crate::input::keyboard::KeyInput;
+use crate::input::mouse::MouseButton;
+
+/// A single input event, carrying the same values the matching
+/// [`EventHandler`](../../event/trait.EventHandler.html) callback receives.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    /// A key was pressed, see `key_down_event()`.
+    KeyDown {
+        /// The key that was pressed.
+        input: KeyInput,
+        /// Whether the key is being held down and this is a repeat.
+        repeat: bool,
+    },
+    /// A key was released, see `key_up_event()`.
+    KeyUp(KeyInput),
+    /// A character was typed, see `text_input_event()`.
+    TextInput(char),
+    /// A mouse button was pressed, see `mouse_button_down_event()`.
+    MouseButtonDown {
+        /// The button that was pressed.
+        button: MouseButton,
+        /// The x position of the cursor.
+        x: f32,
+        /// The y position of the cursor.
+        y: f32,
+    },
+    /// A mouse button was released, see `mouse_button_up_event()`.
+    MouseButtonUp {
+        /// The button that was released.
+        button: MouseButton,
+        /// The x position of the cursor.
+        x: f32,
+        /// The y position of the cursor.
+        y: f32,
+    },
+    /// The mouse cursor moved, see `mouse_motion_event()`.
+    MouseMotion {
+        /// The new x position of the cursor.
+        x: f32,
+        /// The new y position of the cursor.
+        y: f32,
+        /// How far the cursor moved horizontally.
+        dx: f32,
+        /// How far the cursor moved vertically.
+        dy: f32,
+    },
+    /// The mouse wheel was scrolled, see `mouse_wheel_event()`.
+    MouseWheel {
+        /// The horizontal scroll amount.
+        x: f32,
+        /// The vertical scroll amount.
+        y: f32,
+    },
+    /// The window gained or lost focus, see `focus_event()`.
+    Focus(bool),
+}
+
+/// Collects the input events of the current frame.
+///
+/// The queue is off by default; once enabled with [`set_enabled()`](#method.set_enabled),
+/// every input event processed by [`event::process_event()`](../../event/fn.process_event.html)
+/// is appended to it, alongside the usual callbacks. Drain it at the top of `update()`.
+/// Events that are not drained are dropped by [`input::end_frame()`](../fn.end_frame.html),
+/// so the queue only ever holds a single frame's worth.
+#[derive(Clone, Debug, Default)]
+pub struct EventQueue {
+    enabled: bool,
+    events: Vec<InputEvent>,
+}
+
+impl EventQueue {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether input events are being recorded.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Starts or stops recording input events. Disabling the queue discards
+    /// any events that weren't drained yet.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.events.clear();
+        }
+    }
+
+    /// Returns the events recorded so far this frame, without removing them.
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+
+    /// Removes and returns the events recorded so far this frame, oldest first.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = InputEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    pub(crate) fn push(&mut self, event: InputEvent) {
+        if self.enabled {
+            self.events.push(event);
+        }
+    }
+
+    /// Takes back the given event if it is the latest one recorded, for input that
+    /// turned out not to be dispatched after all.
+    pub(crate) fn retract(&mut self, event: &InputEvent) {
+        if self.events.last() == Some(event) {
+            let _ = self.events.pop();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_records_only_when_enabled() {
+        let mut queue = EventQueue::new();
+        queue.push(InputEvent::TextInput('a'));
+        assert!(queue.events().is_empty());
+
+        queue.set_enabled(true);
+        queue.push(InputEvent::TextInput('b'));
+        queue.push(InputEvent::Focus(false));
+        assert_eq!(
+            queue.drain_events().collect::<Vec<_>>(),
+            vec![InputEvent::TextInput('b'), InputEvent::Focus(false)]
+        );
+        assert!(queue.events().is_empty());
+
+        queue.push(InputEvent::MouseWheel { x: 0.0, y: 1.0 });
+        queue.set_enabled(false);
+        assert!(queue.events().is_empty());
+    }
+
+    #[test]
+    fn retract_only_takes_back_the_latest_event() {
+        let mut queue = EventQueue::new();
+        queue.set_enabled(true);
+        queue.push(InputEvent::TextInput('a'));
+        queue.push(InputEvent::Focus(true));
+
+        queue.retract(&InputEvent::TextInput('a'));
+        assert_eq!(queue.events().len(), 2);
+        queue.retract(&InputEvent::Focus(true));
+        assert_eq!(queue.events(), [InputEvent::TextInput('a')]);
+    }
+}
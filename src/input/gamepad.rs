@@ -6,20 +6,80 @@
 #![cfg(feature = "gamepad")]
 
 use gilrs::ConnectedGamepadsIterator;
+use std::collections::HashMap;
 use std::fmt;
+use std::time::Duration;
 
-pub use gilrs::{self, Event, Gamepad, Gilrs};
+pub use gilrs::{self, Event, Gamepad, Gilrs, PowerInfo};
 
 /// A unique identifier for a particular gamepad
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GamepadId(pub(crate) gilrs::GamepadId);
 
 use crate::context::Context;
-use crate::error::GameResult;
+use crate::error::{GameError, GameResult};
+
+/// One of the two analog triggers of a gamepad.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TriggerSide {
+    /// The left trigger, usually labeled `L2` or `LT`.
+    Left,
+    /// The right trigger, usually labeled `R2` or `RT`.
+    Right,
+}
+
+/// How the raw position of a gamepad axis maps to the value passed to
+/// [`gamepad_axis_event()`](../../event/trait.EventHandler.html#method.gamepad_axis_event);
+/// see [`GamepadContext::set_axis_response()`](struct.GamepadContext.html#method.set_axis_response).
+///
+/// Curves are applied to the distance from the center, from `0.0` to `1.0`,
+/// and the sign of the axis is kept.
+#[derive(Copy, Clone, Debug, Default)]
+pub enum ResponseCurve {
+    /// The value is passed through unchanged.
+    #[default]
+    Linear,
+    /// The value is squared, for finer control near the center and fast movement at the edge.
+    Quadratic,
+    /// A custom function mapping `0.0..=1.0` onto `0.0..=1.0`.
+    Custom(fn(f32) -> f32),
+}
+
+impl ResponseCurve {
+    /// Applies the curve to an axis value from `-1.0` to `1.0`.
+    pub fn apply(self, value: f32) -> f32 {
+        let magnitude = value.abs().min(1.0);
+        let mapped = match self {
+            ResponseCurve::Linear => magnitude,
+            ResponseCurve::Quadratic => magnitude * magnitude,
+            ResponseCurve::Custom(f) => f(magnitude).clamp(0.0, 1.0),
+        };
+        mapped.copysign(value)
+    }
+}
+
+/// The state of all buttons and axes of a gamepad at one point in time,
+/// see [`GamepadContext::snapshot()`](struct.GamepadContext.html#method.snapshot).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GamepadSnapshot {
+    /// The buttons that are held down.
+    pub pressed: Vec<gilrs::Button>,
+    /// The value of every button reported so far, from `0.0` to `1.0`.
+    /// Analog triggers report values in between.
+    pub button_values: Vec<(gilrs::Button, f32)>,
+    /// The value of every axis reported so far, with the
+    /// [axis response curve](struct.GamepadContext.html#method.set_axis_response) applied.
+    pub axes: Vec<(gilrs::Axis, f32)>,
+}
 
 /// A structure that contains gamepad state using `gilrs`.
 pub struct GamepadContext {
     pub(crate) gilrs: Gilrs,
+    enabled: bool,
+    mappings: String,
+    slots: HashMap<GamepadId, u8>,
+    axis_responses: HashMap<gilrs::Axis, ResponseCurve>,
+    rumble: HashMap<GamepadId, gilrs::ff::Effect>,
 }
 
 impl fmt::Debug for GamepadContext {
@@ -31,21 +91,146 @@ impl fmt::Debug for GamepadContext {
 impl GamepadContext {
     pub(crate) fn new() -> GameResult<Self> {
         let gilrs = Gilrs::new()?;
-        Ok(GamepadContext { gilrs })
+        Ok(GamepadContext {
+            gilrs,
+            enabled: true,
+            mappings: String::new(),
+            slots: HashMap::new(),
+            axis_responses: HashMap::new(),
+            rumble: HashMap::new(),
+        })
     }
 }
 
 impl From<Gilrs> for GamepadContext {
     /// Converts from a `Gilrs` custom instance to a `GilrsGamepadContext`
     fn from(gilrs: Gilrs) -> Self {
-        Self { gilrs }
+        Self {
+            gilrs,
+            enabled: true,
+            mappings: String::new(),
+            slots: HashMap::new(),
+            axis_responses: HashMap::new(),
+            rumble: HashMap::new(),
+        }
     }
 }
 
 impl GamepadContext {
     /// Returns a gamepad event.
+    ///
+    /// Always returns `None` while the context is disabled.
     pub fn next_event(&mut self) -> Option<Event> {
-        self.gilrs.next_event()
+        if !self.enabled {
+            return None;
+        }
+        let event = self.gilrs.next_event()?;
+        if event.event == gilrs::EventType::Connected {
+            self.reclaim_slot(GamepadId(event.id));
+        }
+        Some(event)
+    }
+
+    /// Discards all pending gamepad events, returning how many there were.
+    ///
+    /// Use this to drop input that was buffered while the game wasn't listening,
+    /// e.g. during a loading screen. The state of the gamepads, as returned by
+    /// [`gamepad()`](#method.gamepad), still reflects the discarded events.
+    pub fn drain_pending(&mut self) -> usize {
+        let mut count = 0;
+        while let Some(event) = self.gilrs.next_event() {
+            if event.event == gilrs::EventType::Connected {
+                self.reclaim_slot(GamepadId(event.id));
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns whether or not gamepad events are polled.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables polling for gamepad events.
+    ///
+    /// While disabled, the event loop doesn't poll `gilrs` at all and no gamepad
+    /// callbacks are called. This can be used to work around platform backends
+    /// that misbehave, without having to turn off the `gamepad` feature.
+    ///
+    /// Events that queued up while disabled are dropped when re-enabling.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            let _ = self.drain_pending();
+        }
+        self.enabled = enabled;
+    }
+
+    /// Assigns a gamepad to a player slot, for local multiplayer.
+    ///
+    /// A slot holds at most one gamepad; if another gamepad had this slot,
+    /// it loses its assignment. Slots stay assigned while a gamepad is
+    /// disconnected, and when a gamepad of the same model connects again under
+    /// a different `GamepadId`, it takes over the slot of the disconnected one.
+    pub fn assign_slot(&mut self, id: GamepadId, slot: u8) {
+        self.slots.retain(|_, s| *s != slot);
+        let _ = self.slots.insert(id, slot);
+    }
+
+    /// Removes the slot assignment of a gamepad, returning the slot it had.
+    pub fn unassign_slot(&mut self, id: GamepadId) -> Option<u8> {
+        self.slots.remove(&id)
+    }
+
+    /// Returns the player slot a gamepad is assigned to, if any.
+    pub fn slot_of(&self, id: GamepadId) -> Option<u8> {
+        self.slots.get(&id).copied()
+    }
+
+    /// Returns the gamepad assigned to a player slot, if any.
+    pub fn gamepad_in_slot(&self, slot: u8) -> Option<GamepadId> {
+        self.slots
+            .iter()
+            .find(|(_, s)| **s == slot)
+            .map(|(id, _)| *id)
+    }
+
+    /// Hands the slot of a disconnected gamepad of the same model to a newly connected one.
+    fn reclaim_slot(&mut self, id: GamepadId) {
+        if self.slots.contains_key(&id) {
+            return;
+        }
+        let uuid = self.gilrs.gamepad(id.0).uuid();
+        let orphan = self
+            .slots
+            .iter()
+            .filter(|(other, _)| {
+                self.gilrs.connected_gamepad(other.0).is_none()
+                    && self.gilrs.gamepad(other.0).uuid() == uuid
+            })
+            .min_by_key(|(_, slot)| **slot)
+            .map(|(other, slot)| (*other, *slot));
+        if let Some((other, slot)) = orphan {
+            let _ = self.slots.remove(&other);
+            let _ = self.slots.insert(id, slot);
+        }
+    }
+
+    /// Sets the response curve of an axis, which the event loop applies to the value
+    /// before calling `gamepad_axis_event()`. All axes start out `Linear`.
+    pub fn set_axis_response(&mut self, axis: gilrs::Axis, curve: ResponseCurve) {
+        let _ = self.axis_responses.insert(axis, curve);
+    }
+
+    /// Returns the response curve of an axis.
+    pub fn axis_response(&self, axis: gilrs::Axis) -> ResponseCurve {
+        self.axis_responses.get(&axis).copied().unwrap_or_default()
+    }
+
+    /// Applies the response curve of an axis to a raw axis value, like the event loop does.
+    /// Useful when reading axes through [`gamepad()`](#method.gamepad) directly.
+    pub fn apply_axis_response(&self, axis: gilrs::Axis, value: f32) -> f32 {
+        self.axis_response(axis).apply(value)
     }
 
     /// Returns the `Gamepad` associated with an `id`.
@@ -53,12 +238,203 @@ impl GamepadContext {
         self.gilrs.gamepad(id.0)
     }
 
+    /// Adds controller mappings in the format of the
+    /// [SDL_GameControllerDB](https://github.com/gabomdq/SDL_GameControllerDB),
+    /// one mapping per line.
+    ///
+    /// Mappings passed here take precedence over the ones included with `gilrs`.
+    /// `gilrs` only reads mappings when it starts, so this restarts it: currently
+    /// connected gamepads are enumerated again and may get new `GamepadId`s,
+    /// which also drops their [player slots](#method.assign_slot),
+    /// and any customization of a `Gilrs` instance this context was created
+    /// from is lost.
+    pub fn load_mappings(&mut self, sdl_db: &str) -> GameResult {
+        let mut mappings = self.mappings.clone();
+        if !mappings.is_empty() && !mappings.ends_with('\n') {
+            mappings.push('\n');
+        }
+        mappings.push_str(sdl_db);
+        self.gilrs = gilrs::GilrsBuilder::new().add_mappings(&mappings).build()?;
+        self.mappings = mappings;
+        self.slots.clear();
+        Ok(())
+    }
+
+    /// Sets the mapping of a connected gamepad, returning it as an SDL mapping
+    /// string which can be saved and passed to [`load_mappings()`](#method.load_mappings)
+    /// later on. If `name` is `None`, the gamepad's name is kept.
+    ///
+    /// See [`Gilrs::set_mapping()`] for details.
+    pub fn set_mapping<'a>(
+        &mut self,
+        id: GamepadId,
+        mapping: &gilrs::Mapping,
+        name: impl Into<Option<&'a str>>,
+    ) -> GameResult<String> {
+        self.gilrs
+            .set_mapping(usize::from(id.0), mapping, name)
+            .map_err(|e| GameError::GamepadError(format!("Could not set mapping: {e}")))
+    }
+
+    /// Returns how far the given trigger of a gamepad is pressed down,
+    /// from `0.0` (released) to `1.0` (fully pressed).
+    ///
+    /// Depending on the controller and platform backend, `gilrs` reports triggers
+    /// either as analog buttons or as axes; this reads whichever is available.
+    /// Digital-only triggers read as either `0.0` or `1.0`.
+    pub fn trigger_value(&self, id: GamepadId, side: TriggerSide) -> f32 {
+        let (button, axis) = match side {
+            TriggerSide::Left => (gilrs::Button::LeftTrigger2, gilrs::Axis::LeftZ),
+            TriggerSide::Right => (gilrs::Button::RightTrigger2, gilrs::Axis::RightZ),
+        };
+        let gamepad = self.gamepad(id);
+        if let Some(data) = gamepad.button_data(button) {
+            data.value().clamp(0.0, 1.0)
+        } else if let Some(data) = gamepad.axis_data(axis) {
+            // axes are reported from -1.0 to 1.0
+            ((data.value() + 1.0) / 2.0).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Makes a gamepad rumble for the given duration, with the strength of its strong
+    /// (low frequency) and weak (high frequency) motors from `0.0` to `1.0`.
+    ///
+    /// See [`play_rumble_pattern()`](#method.play_rumble_pattern) for more details.
+    pub fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> GameResult {
+        self.play_rumble_pattern(id, &[(strong, weak, duration)])
+    }
+
+    /// Plays a sequence of rumble steps on a gamepad, like a heartbeat or the aftershock of
+    /// an explosion. Each step is `(strong, weak, duration)`, with the strength of the strong
+    /// and weak motors from `0.0` to `1.0`; use a step with zero strength for a pause.
+    ///
+    /// The whole pattern is handed to `gilrs` at once, which times the steps on its own,
+    /// so it keeps going without any further calls. Playing another pattern on the same
+    /// gamepad replaces the current one. Returns an error if the gamepad doesn't support
+    /// force feedback.
+    pub fn play_rumble_pattern(
+        &mut self,
+        id: GamepadId,
+        pattern: &[(f32, f32, Duration)],
+    ) -> GameResult {
+        use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Repeat, Replay, Ticks};
+
+        if !self.gamepad(id).is_ff_supported() {
+            return Err(GameError::GamepadError(String::from(
+                "The gamepad doesn't support force feedback",
+            )));
+        }
+        let total = pattern
+            .iter()
+            .map(|&(_, _, duration)| Ticks::from(duration))
+            .fold(Ticks::from_ms(0), |total, duration| total + duration);
+        let magnitude = |strength: f32| (strength.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16;
+
+        let mut builder = EffectBuilder::new();
+        let mut after = Ticks::from_ms(0);
+        for &(strong, weak, duration) in pattern {
+            let scheduling = Replay {
+                after,
+                play_for: Ticks::from(duration),
+                // never repeat within the pattern
+                with_delay: total,
+            };
+            for kind in [
+                BaseEffectType::Strong {
+                    magnitude: magnitude(strong),
+                },
+                BaseEffectType::Weak {
+                    magnitude: magnitude(weak),
+                },
+            ] {
+                let _ = builder.add_effect(BaseEffect {
+                    kind,
+                    scheduling,
+                    ..BaseEffect::default()
+                });
+            }
+            after += Ticks::from(duration);
+        }
+        let effect = builder
+            .gamepads(&[id.0])
+            .repeat(Repeat::For(total))
+            .finish(&mut self.gilrs)
+            .map_err(|e| GameError::GamepadError(format!("Could not create rumble effect: {e}")))?;
+        effect
+            .play()
+            .map_err(|e| GameError::GamepadError(format!("Could not play rumble effect: {e}")))?;
+        let _ = self.rumble.insert(id, effect);
+        Ok(())
+    }
+
+    /// Stops any rumble playing on a gamepad.
+    pub fn stop_rumble(&mut self, id: GamepadId) {
+        // dropping the last handle to an effect stops it
+        let _ = self.rumble.remove(&id);
+    }
+
+    /// Returns the power supply state of a gamepad: whether it is wired,
+    /// or the charge of its battery, if the platform backend reports it.
+    pub fn power_info(&self, id: GamepadId) -> PowerInfo {
+        self.gamepad(id).power_info()
+    }
+
+    /// Returns the current state of all buttons and axes of the given gamepad.
+    ///
+    /// Useful right after a gamepad connects, as buttons or triggers that are
+    /// already held down don't cause any events until they change.
+    /// Elements the platform hasn't reported a value for yet are left out.
+    pub fn snapshot(&self, id: GamepadId) -> GamepadSnapshot {
+        let gamepad = self.gamepad(id);
+        let mut snapshot = GamepadSnapshot::default();
+        for (code, data) in gamepad.state().buttons() {
+            if let Some(gilrs::ev::AxisOrBtn::Btn(button)) = gamepad.axis_or_btn_name(code) {
+                if data.is_pressed() {
+                    snapshot.pressed.push(button);
+                }
+                snapshot.button_values.push((button, data.value()));
+            }
+        }
+        for (code, data) in gamepad.state().axes() {
+            if let Some(gilrs::ev::AxisOrBtn::Axis(axis)) = gamepad.axis_or_btn_name(code) {
+                snapshot
+                    .axes
+                    .push((axis, self.apply_axis_response(axis, data.value())));
+            }
+        }
+        snapshot
+    }
+
     /// Return an iterator of all the `Gamepads` that are connected.
     pub fn gamepads(&self) -> GamepadsIterator {
         GamepadsIterator {
             wrapped: self.gilrs.gamepads(),
         }
     }
+
+    /// Writes the gamepad section of [`Context::debug_dump()`](crate::Context::debug_dump).
+    pub(crate) fn write_debug_dump(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        writeln!(out, "[gamepad]")?;
+        writeln!(out, "  enabled: {}", self.enabled)?;
+        for (id, gamepad) in self.gamepads() {
+            writeln!(
+                out,
+                "  {:?}: {} ({:?})",
+                id,
+                gamepad.name(),
+                gamepad.power_info()
+            )?;
+        }
+        Ok(())
+    }
 }
 
 /// An iterator of the connected gamepads
@@ -124,4 +500,16 @@ mod tests {
     fn gilrs_init() {
         assert!(GamepadContext::new().is_ok());
     }
+
+    #[test]
+    fn response_curves() {
+        assert_eq!(ResponseCurve::Linear.apply(-0.5), -0.5);
+        assert_eq!(ResponseCurve::Quadratic.apply(0.5), 0.25);
+        assert_eq!(ResponseCurve::Quadratic.apply(-0.5), -0.25);
+        assert_eq!(ResponseCurve::Quadratic.apply(-1.5), -1.0);
+        let cube = ResponseCurve::Custom(|x| x * x * x);
+        assert_eq!(cube.apply(-0.5), -0.125);
+        let broken = ResponseCurve::Custom(|x| x * 4.0);
+        assert_eq!(broken.apply(0.5), 1.0);
+    }
 }
@@ -109,7 +109,8 @@
 
 use crate::context::Context;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use winit::event::ModifiersState;
 pub use winit::event::ScanCode;
 /// A key code.
@@ -152,16 +153,145 @@ impl From<ModifiersState> for KeyMods {
     }
 }
 
-/// A simple wrapper bundling the four properties of a keyboard stroke.
-#[derive(Copy, Clone, Debug)]
+/// A simple wrapper bundling the properties of a keyboard stroke.
+///
+/// More properties may be added in the future, so it can't be built with a struct
+/// literal outside of ggez; use [`TestDriver`](crate::event::TestDriver) to feed
+/// keystrokes to an event handler in tests.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub struct KeyInput {
     /// The scancode. For more info on what they are and when to use them refer to the
     /// [`keyboard`](crate::input::keyboard) module.
     pub scancode: ScanCode,
     /// The keycode corresponding to the scancode, if there is one.
+    ///
+    /// Keys that winit can't identify, like the extra buttons on some keypads, have no
+    /// keycode; they can still be told apart, and bound, by their `scancode`.
     pub keycode: Option<KeyCode>,
     /// The keyboard modifiers active at the moment of input.
     pub mods: KeyMods,
+    /// When the input was processed, if known.
+    /// See [`Context::event_timestamp()`](crate::Context::event_timestamp).
+    pub timestamp: Option<std::time::Instant>,
+}
+
+impl KeyInput {
+    /// Returns a human readable description of the keystroke including its modifiers,
+    /// such as `"Ctrl + Shift + A"`, for showing key bindings to the player.
+    ///
+    /// Keystrokes without a keycode are shown by their scancode.
+    pub fn display_string(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let logo = if cfg!(target_os = "macos") {
+            "Cmd"
+        } else {
+            "Win"
+        };
+        let modifiers = [
+            (
+                KeyMods::CTRL,
+                "Ctrl",
+                [KeyCode::LControl, KeyCode::RControl],
+            ),
+            (KeyMods::SHIFT, "Shift", [KeyCode::LShift, KeyCode::RShift]),
+            (KeyMods::ALT, "Alt", [KeyCode::LAlt, KeyCode::RAlt]),
+            (KeyMods::LOGO, logo, [KeyCode::LWin, KeyCode::RWin]),
+        ];
+        for (modifier, name, keys) in modifiers {
+            // don't show a modifier twice when it's the key itself
+            let is_key = self.keycode.is_some_and(|key| keys.contains(&key));
+            if self.mods.contains(modifier) && !is_key {
+                parts.push(name.to_string());
+            }
+        }
+        parts.push(match self.keycode {
+            Some(key) => key_name(key),
+            None => format!("Scancode {}", self.scancode),
+        });
+        parts.join(" + ")
+    }
+}
+
+/// Returns a human readable name for a key, such as `"Left Ctrl"` or `"Page Up"`.
+pub fn key_name(key: KeyCode) -> String {
+    let name = match key {
+        KeyCode::Key0 => "0",
+        KeyCode::Key1 => "1",
+        KeyCode::Key2 => "2",
+        KeyCode::Key3 => "3",
+        KeyCode::Key4 => "4",
+        KeyCode::Key5 => "5",
+        KeyCode::Key6 => "6",
+        KeyCode::Key7 => "7",
+        KeyCode::Key8 => "8",
+        KeyCode::Key9 => "9",
+        KeyCode::Escape => "Esc",
+        KeyCode::Snapshot => "Print Screen",
+        KeyCode::Scroll => "Scroll Lock",
+        KeyCode::Back => "Backspace",
+        KeyCode::Return => "Enter",
+        KeyCode::Numlock => "Num Lock",
+        KeyCode::Capital => "Caps Lock",
+        KeyCode::LAlt => "Left Alt",
+        KeyCode::RAlt => "Right Alt",
+        KeyCode::LControl => "Left Ctrl",
+        KeyCode::RControl => "Right Ctrl",
+        KeyCode::LShift => "Left Shift",
+        KeyCode::RShift => "Right Shift",
+        KeyCode::LWin if cfg!(target_os = "macos") => "Left Cmd",
+        KeyCode::RWin if cfg!(target_os = "macos") => "Right Cmd",
+        KeyCode::LWin => "Left Win",
+        KeyCode::RWin => "Right Win",
+        KeyCode::LBracket => "[",
+        KeyCode::RBracket => "]",
+        KeyCode::Apostrophe => "'",
+        KeyCode::Asterisk => "*",
+        KeyCode::At => "@",
+        KeyCode::Backslash => "\\",
+        KeyCode::Colon => ":",
+        KeyCode::Comma => ",",
+        KeyCode::Equals => "=",
+        KeyCode::Grave => "`",
+        KeyCode::Minus => "-",
+        KeyCode::Period => ".",
+        KeyCode::Plus => "+",
+        KeyCode::Semicolon => ";",
+        KeyCode::Slash => "/",
+        KeyCode::Numpad0 => "Numpad 0",
+        KeyCode::Numpad1 => "Numpad 1",
+        KeyCode::Numpad2 => "Numpad 2",
+        KeyCode::Numpad3 => "Numpad 3",
+        KeyCode::Numpad4 => "Numpad 4",
+        KeyCode::Numpad5 => "Numpad 5",
+        KeyCode::Numpad6 => "Numpad 6",
+        KeyCode::Numpad7 => "Numpad 7",
+        KeyCode::Numpad8 => "Numpad 8",
+        KeyCode::Numpad9 => "Numpad 9",
+        KeyCode::NumpadAdd => "Numpad +",
+        KeyCode::NumpadDivide => "Numpad /",
+        KeyCode::NumpadDecimal => "Numpad .",
+        KeyCode::NumpadComma => "Numpad ,",
+        KeyCode::NumpadEquals => "Numpad =",
+        KeyCode::NumpadMultiply => "Numpad *",
+        KeyCode::NumpadSubtract => "Numpad -",
+        _ => {
+            // the remaining names only need spaces between their words,
+            // e.g. `PageUp` or `NumpadEnter`; acronyms like `OEM102` stay together
+            let debug = format!("{key:?}");
+            let mut name = String::with_capacity(debug.len() + 4);
+            let mut prev = None;
+            for c in debug.chars() {
+                if c.is_ascii_uppercase() && prev.is_some_and(|p: char| p.is_ascii_lowercase()) {
+                    name.push(' ');
+                }
+                name.push(c);
+                prev = Some(c);
+            }
+            return name;
+        }
+    };
+    name.to_string()
 }
 
 /// Tracks held down keyboard keys, active keyboard modifiers,
@@ -174,6 +304,8 @@ pub struct KeyboardContext {
     /// integers is unsafe and a set really is what we want anyway.
     pressed_keys_set: HashSet<KeyCode>,
     pressed_scancodes_set: HashSet<ScanCode>,
+    // The keycode each held scancode was pressed with, to release it again on focus loss.
+    held_keystrokes: HashMap<ScanCode, Option<KeyCode>>,
 
     // These two are necessary for tracking key-repeat.
     last_pressed: Option<ScanCode>,
@@ -182,6 +314,12 @@ pub struct KeyboardContext {
     // Represents the state of pressed_keys_set last frame.
     previously_pressed_keys_set: HashSet<KeyCode>,
     previously_pressed_scancodes_set: HashSet<ScanCode>,
+
+    // When each held key was first pressed, ignoring key repeats.
+    pressed_since: HashMap<KeyCode, Instant>,
+
+    // How many text fields and the like currently own the keyboard.
+    input_captures: usize,
 }
 
 impl KeyboardContext {
@@ -191,18 +329,50 @@ impl KeyboardContext {
             // We just use 256 as a number Big Enough For Keyboard Keys to try to avoid resizing.
             pressed_keys_set: HashSet::with_capacity(256),
             pressed_scancodes_set: HashSet::with_capacity(256),
+            held_keystrokes: HashMap::new(),
             last_pressed: None,
             current_pressed: None,
             previously_pressed_keys_set: HashSet::with_capacity(256),
             previously_pressed_scancodes_set: HashSet::with_capacity(256),
+            pressed_since: HashMap::new(),
+            input_captures: 0,
         }
     }
 
+    /// Marks the keyboard as owned by text entry, e.g. when a text field gains focus.
+    ///
+    /// While any capture is active, the default
+    /// [`key_down_event()`](crate::event::EventHandler::key_down_event) doesn't quit on
+    /// the [quit key](crate::Context::set_quit_key), so Escape can cancel the text field
+    /// instead. Captures stack, so nested widgets can each push and pop their own.
+    pub fn push_input_capture(&mut self) {
+        self.input_captures += 1;
+    }
+
+    /// Releases a capture made with [`push_input_capture()`](#method.push_input_capture),
+    /// e.g. when a text field loses focus.
+    pub fn pop_input_capture(&mut self) {
+        self.input_captures = self.input_captures.saturating_sub(1);
+    }
+
+    /// Returns whether or not the keyboard is currently captured by text entry,
+    /// see [`push_input_capture()`](#method.push_input_capture).
+    pub fn is_input_captured(&self) -> bool {
+        self.input_captures > 0
+    }
+
     /// Checks if a key is currently pressed down.
     pub fn is_key_pressed(&self, key: KeyCode) -> bool {
         self.pressed_keys_set.contains(&key)
     }
 
+    /// Returns for how long a key has been held down, or `None` if it isn't pressed.
+    ///
+    /// Key repeats sent by the system while the key is held don't restart the duration.
+    pub fn held_duration(&self, key: KeyCode) -> Option<Duration> {
+        self.pressed_since.get(&key).map(Instant::elapsed)
+    }
+
     /// Checks if a key has been pressed down this frame.
     pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
         self.pressed_keys_set.contains(&key) && !self.previously_pressed_keys_set.contains(&key)
@@ -260,9 +430,30 @@ impl KeyboardContext {
         self.active_modifiers
     }
 
+    /// Checks if either Shift key is held down.
+    pub fn shift(&self) -> bool {
+        self.is_mod_active(KeyMods::SHIFT)
+    }
+
+    /// Checks if either Control key is held down.
+    pub fn ctrl(&self) -> bool {
+        self.is_mod_active(KeyMods::CTRL)
+    }
+
+    /// Checks if either Alt key is held down.
+    pub fn alt(&self) -> bool {
+        self.is_mod_active(KeyMods::ALT)
+    }
+
+    /// Checks if either Win/Cmd/equivalent key is held down.
+    pub fn logo(&self) -> bool {
+        self.is_mod_active(KeyMods::LOGO)
+    }
+
     /// Copies the current state of the keyboard into the context. If you are writing your own event loop
     /// you need to call this at the end of every update in order to use the functions `is_key_just_pressed`
     /// and `is_key_just_released`. Otherwise this is handled for you.
+    /// [`input::end_frame()`](../fn.end_frame.html) calls this along with the mouse equivalents.
     pub fn save_keyboard_state(&mut self) {
         self.previously_pressed_keys_set = self.pressed_keys_set.clone();
         self.previously_pressed_scancodes_set = self.pressed_scancodes_set.clone();
@@ -271,13 +462,50 @@ impl KeyboardContext {
     pub(crate) fn set_key(&mut self, key: KeyCode, pressed: bool) {
         if pressed {
             let _ = self.pressed_keys_set.insert(key);
+            let _ = self.pressed_since.entry(key).or_insert_with(Instant::now);
         } else {
             let _ = self.pressed_keys_set.remove(&key);
+            let _ = self.pressed_since.remove(&key);
         }
 
         self.set_key_modifier(key, pressed);
     }
 
+    /// Records a keystroke as sent by the window, updating both its scancode and keycode.
+    pub(crate) fn set_keystroke(&mut self, code: ScanCode, key: Option<KeyCode>, pressed: bool) {
+        self.set_scancode(code, pressed);
+        if let Some(key) = key {
+            self.set_key(key, pressed);
+        }
+        if pressed {
+            let _ = self.held_keystrokes.insert(code, key);
+        } else {
+            let _ = self.held_keystrokes.remove(&code);
+        }
+    }
+
+    /// Releases every held key, returning the scancode and keycode of each keystroke that
+    /// was held, ordered by scancode.
+    /// Used when the window loses focus, since the key-up events are sent elsewhere then.
+    pub(crate) fn release_all(&mut self) -> Vec<(ScanCode, Option<KeyCode>)> {
+        let mut keystrokes: Vec<(ScanCode, Option<KeyCode>)> =
+            self.held_keystrokes.drain().collect();
+        keystrokes.sort();
+        // keys that were only set by their keycode, without a keystroke of their own
+        let mut keys: Vec<KeyCode> = self
+            .pressed_keys_set
+            .drain()
+            .filter(|&key| !keystrokes.iter().any(|&(_, k)| k == Some(key)))
+            .collect();
+        keys.sort();
+        keystrokes.extend(keys.into_iter().map(|key| (0, Some(key))));
+        self.pressed_since.clear();
+        self.pressed_scancodes_set.clear();
+        self.current_pressed = None;
+        self.active_modifiers = KeyMods::empty();
+        keystrokes
+    }
+
     pub(crate) fn set_scancode(&mut self, code: ScanCode, pressed: bool) {
         if pressed {
             let _ = self.pressed_scancodes_set.insert(code);
@@ -408,6 +636,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn input_captures_stack() {
+        let mut keyboard = KeyboardContext::new();
+        assert!(!keyboard.is_input_captured());
+        keyboard.push_input_capture();
+        keyboard.push_input_capture();
+        keyboard.pop_input_capture();
+        assert!(keyboard.is_input_captured());
+        keyboard.pop_input_capture();
+        keyboard.pop_input_capture();
+        assert!(!keyboard.is_input_captured());
+    }
+
+    #[test]
+    fn key_display_strings() {
+        assert_eq!(key_name(KeyCode::A), "A");
+        assert_eq!(key_name(KeyCode::Key7), "7");
+        assert_eq!(key_name(KeyCode::PageUp), "Page Up");
+        assert_eq!(key_name(KeyCode::LControl), "Left Ctrl");
+        assert_eq!(key_name(KeyCode::NumpadEnter), "Numpad Enter");
+        assert_eq!(key_name(KeyCode::OEM102), "OEM102");
+        assert_eq!(key_name(KeyCode::F12), "F12");
+        assert_eq!(key_name(KeyCode::AbntC1), "Abnt C1");
+
+        let input = KeyInput {
+            scancode: 0,
+            keycode: Some(KeyCode::A),
+            mods: KeyMods::CTRL | KeyMods::SHIFT,
+            timestamp: None,
+        };
+        assert_eq!(input.display_string(), "Ctrl + Shift + A");
+        let input = KeyInput {
+            scancode: 0,
+            keycode: Some(KeyCode::LControl),
+            mods: KeyMods::CTRL,
+            timestamp: None,
+        };
+        assert_eq!(input.display_string(), "Left Ctrl");
+        let input = KeyInput {
+            scancode: 42,
+            keycode: None,
+            mods: KeyMods::ALT,
+            timestamp: None,
+        };
+        assert_eq!(input.display_string(), "Alt + Scancode 42");
+    }
+
     #[test]
     fn pressed_keys_tracking() {
         let mut keyboard = KeyboardContext::new();
@@ -513,6 +788,54 @@ mod tests {
         assert_eq!(keyboard.active_mods(), KeyMods::SHIFT | KeyMods::LOGO);
         keyboard.set_key(KeyCode::LWin, false);
         assert_eq!(keyboard.active_mods(), KeyMods::SHIFT);
+        assert!(keyboard.shift());
+        assert!(!keyboard.ctrl());
+        assert!(!keyboard.alt());
+        assert!(!keyboard.logo());
+    }
+
+    #[test]
+    fn release_all_keys() {
+        let mut keyboard = KeyboardContext::new();
+        keyboard.set_keystroke(42, Some(KeyCode::LShift), true);
+        keyboard.set_keystroke(30, Some(KeyCode::A), true);
+        // a key winit has no keycode for
+        keyboard.set_keystroke(200, None, true);
+        keyboard.save_keyboard_state();
+        assert_eq!(
+            keyboard.release_all(),
+            vec![
+                (30, Some(KeyCode::A)),
+                (42, Some(KeyCode::LShift)),
+                (200, None)
+            ]
+        );
+        assert!(keyboard.pressed_keys().is_empty());
+        assert!(keyboard.pressed_scancodes().is_empty());
+        assert!(!keyboard.shift());
+        assert!(keyboard.is_key_just_released(KeyCode::A));
+        assert!(keyboard.is_scancode_just_released(200));
+        assert!(keyboard.release_all().is_empty());
+
+        // a released keystroke isn't released again
+        keyboard.set_keystroke(30, Some(KeyCode::A), true);
+        keyboard.set_keystroke(30, Some(KeyCode::A), false);
+        assert!(keyboard.release_all().is_empty());
+    }
+
+    #[test]
+    fn held_duration_tracking() {
+        let mut keyboard = KeyboardContext::new();
+        assert_eq!(keyboard.held_duration(KeyCode::Space), None);
+        keyboard.set_key(KeyCode::Space, true);
+        std::thread::sleep(Duration::from_millis(10));
+        let held = keyboard.held_duration(KeyCode::Space).unwrap();
+        assert!(held >= Duration::from_millis(10));
+        // a key repeat doesn't restart the duration
+        keyboard.set_key(KeyCode::Space, true);
+        assert!(keyboard.held_duration(KeyCode::Space).unwrap() >= held);
+        keyboard.set_key(KeyCode::Space, false);
+        assert_eq!(keyboard.held_duration(KeyCode::Space), None);
     }
 
     #[test]
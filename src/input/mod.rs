@@ -1,4 +1,25 @@
 //! Input handling modules for keyboard, mouse and gamepad.
+pub mod bindings;
+mod debounce;
+mod events;
 pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+
+pub use self::debounce::Debouncer;
+pub use self::events::{EventQueue, InputEvent};
+
+use crate::context::Context;
+
+/// Finishes the input state of the current frame.
+///
+/// Resets the mouse delta, drops this frame's [`InputEvent`]s and saves the keyboard and mouse state, which `is_key_just_pressed()`,
+/// `button_just_released()` and friends compare against on the next frame.
+/// [`event::run()`](../event/fn.run.html) calls this for you; if you are writing your own
+/// event loop, this is the one call you need at the end of every frame, after `update` and `draw`.
+pub fn end_frame(ctx: &mut Context) {
+    ctx.mouse.reset_delta();
+    ctx.input_events.clear();
+    ctx.keyboard.save_keyboard_state();
+    ctx.mouse.save_mouse_state();
+}
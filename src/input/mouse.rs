@@ -1,8 +1,9 @@
 //! Mouse utility functions.
 
-use crate::context::Context;
+use crate::context::{Context, Has};
 use crate::error::GameError;
 use crate::error::GameResult;
+use crate::graphics::{GraphicsContext, Rect};
 use std::collections::HashSet;
 use winit::dpi;
 pub use winit::event::MouseButton;
@@ -21,6 +22,13 @@ pub struct MouseContext {
     cursor_grabbed: bool,
     cursor_hidden: bool,
     previous_buttons_pressed: HashSet<MouseButton>,
+    drag_start: Option<glam::Vec2>,
+    raw_delta: glam::DVec2,
+    last_raw_delta: glam::DVec2,
+    raw_sensitivity: glam::DVec2,
+    raw_invert_y: bool,
+    coalesce_motion: bool,
+    motion_pending: bool,
 }
 
 impl MouseContext {
@@ -34,6 +42,13 @@ impl MouseContext {
             cursor_grabbed: false,
             cursor_hidden: false,
             previous_buttons_pressed: HashSet::new(),
+            drag_start: None,
+            raw_delta: glam::DVec2::ZERO,
+            last_raw_delta: glam::DVec2::ZERO,
+            raw_sensitivity: glam::DVec2::ONE,
+            raw_invert_y: false,
+            coalesce_motion: false,
+            motion_pending: false,
         }
     }
 
@@ -42,25 +57,79 @@ impl MouseContext {
         self.cursor_type
     }
 
+    /// Modifies the mouse cursor type of the window, choosing from the
+    /// standard cursors provided by the OS (such as `Text`, `Grab` or `Crosshair`).
+    pub fn set_cursor_type(&mut self, gfx: &impl Has<GraphicsContext>, cursor_type: CursorIcon) {
+        self.cursor_type = cursor_type;
+        gfx.retrieve().window.set_cursor_icon(cursor_type);
+    }
+
     /// Set whether or not the mouse is hidden (invisible)
     pub fn cursor_hidden(&self) -> bool {
         self.cursor_hidden
     }
 
     /// Get the current position of the mouse cursor, in pixels.
-    /// Complement to [`set_position()`](fn.set_position.html).
+    /// Complement to [`set_position()`](#method.set_position).
     /// Uses strictly window-only coordinates.
     pub fn position(&self) -> mint::Point2<f32> {
         self.last_position.into()
     }
 
+    /// Moves the mouse cursor to the given position, in pixels.
+    /// Uses strictly window-only coordinates.
+    ///
+    /// The value returned by [`position()`](#method.position) is updated right away,
+    /// so the `CursorMoved` event the platform sends in response doesn't show up
+    /// as movement in [`delta()`](#method.delta). Together with
+    /// [`set_cursor_grabbed()`](fn.set_cursor_grabbed.html) this can be used to
+    /// re-center the cursor every frame for mouselook.
+    ///
+    /// ### Errors
+    ///
+    /// Will return `GameError::WindowError` if the platform doesn't support
+    /// moving the cursor (Wayland, for example).
+    pub fn set_position(&mut self, gfx: &impl Has<GraphicsContext>, x: f32, y: f32) -> GameResult {
+        gfx.retrieve()
+            .window
+            .set_cursor_position(dpi::PhysicalPosition {
+                x: f64::from(x),
+                y: f64::from(y),
+            })
+            .map_err(|e| {
+                GameError::WindowError(format!("Couldn't set mouse cursor position: {e}"))
+            })?;
+        self.last_position = glam::Vec2::new(x, y);
+        Ok(())
+    }
+
+    /// Returns the current position of the mouse cursor in the given screen coordinates.
+    ///
+    /// Pass the same rect you give to
+    /// [`Canvas::set_screen_coordinates()`](../../graphics/struct.Canvas.html#method.set_screen_coordinates)
+    /// (for example a virtual resolution padded for letterboxing), and the physical cursor
+    /// position is mapped through it, so you get the point in game space the cursor is over.
+    /// The screen coordinates are a property of each canvas, so the graphics context can't
+    /// know them on its own.
+    pub fn virtual_position(
+        &self,
+        gfx: &impl Has<GraphicsContext>,
+        screen: Rect,
+    ) -> mint::Point2<f32> {
+        let (width, height) = gfx.retrieve().drawable_size();
+        to_screen_coordinates(self.last_position, width, height, screen).into()
+    }
+
     /// Get the distance the cursor was moved during the current frame, in pixels.
     pub fn delta(&self) -> mint::Point2<f32> {
         self.delta.into()
     }
 
     /// Returns whether or not the given mouse button is pressed.
-
+    ///
+    /// Extra buttons, such as the back and forward buttons found on the side
+    /// of many mice, are reported by `winit` as [`MouseButton::Other`] and
+    /// are tracked just like the primary buttons.
     pub fn button_pressed(&self, button: MouseButton) -> bool {
         self.buttons_pressed.contains(&button)
     }
@@ -75,6 +144,55 @@ impl MouseContext {
         !self.buttons_pressed.contains(&button) && self.previous_buttons_pressed.contains(&button)
     }
 
+    /// Get the raw mouse motion accumulated during the current frame.
+    ///
+    /// Unlike [`delta()`](#method.delta) this isn't derived from the cursor
+    /// position, so it keeps reporting movement while the cursor is grabbed or
+    /// pinned against the edge of the screen. The values are in device units
+    /// and already scaled by [`set_raw_sensitivity()`](#method.set_raw_sensitivity).
+    pub fn raw_delta(&self) -> mint::Vector2<f64> {
+        self.raw_delta.into()
+    }
+
+    /// Returns the scaling factors applied to raw mouse motion.
+    pub fn raw_sensitivity(&self) -> (f64, f64) {
+        (self.raw_sensitivity.x, self.raw_sensitivity.y)
+    }
+
+    /// Sets the scaling factors applied to raw mouse motion, before it is
+    /// passed to [`raw_mouse_motion_event`](../../event/trait.EventHandler.html#method.raw_mouse_motion_event)
+    /// and accumulated into [`raw_delta()`](#method.raw_delta).
+    ///
+    /// The default is `1.0` for both axes.
+    pub fn set_raw_sensitivity(&mut self, x: f64, y: f64) {
+        self.raw_sensitivity = glam::DVec2::new(x, y);
+    }
+
+    /// Returns whether or not the Y axis of raw mouse motion is inverted.
+    pub fn raw_invert_y(&self) -> bool {
+        self.raw_invert_y
+    }
+
+    /// Sets whether or not the Y axis of raw mouse motion should be inverted.
+    pub fn set_raw_invert_y(&mut self, invert: bool) {
+        self.raw_invert_y = invert;
+    }
+
+    /// Returns the position where the primary (left) mouse button went down,
+    /// if it is currently being held.
+    ///
+    /// This is reset to `None` as soon as the button is released.
+    pub fn drag_start(&self) -> Option<mint::Point2<f32>> {
+        self.drag_start.map(Into::into)
+    }
+
+    /// Returns the distance the cursor has moved since the primary (left)
+    /// mouse button went down, if it is currently being held.
+    pub fn drag_delta(&self) -> Option<mint::Point2<f32>> {
+        self.drag_start
+            .map(|start| (self.last_position - start).into())
+    }
+
     /// Updates delta and position values.
     /// The inputs are interpreted as pixel coordinates inside the window.
     ///
@@ -102,16 +220,56 @@ impl MouseContext {
         self.set_last_position(glam::Vec2::new(new_x, new_y));
     }
 
-    /// Resets the value returned by [`mouse::delta`](fn.delta.html) to zero.
+    /// Returns whether or not cursor motion is coalesced into a single
+    /// `mouse_motion_event` per frame.
+    pub fn coalesce_motion(&self) -> bool {
+        self.coalesce_motion
+    }
+
+    /// Sets whether or not cursor motion is coalesced into a single
+    /// [`mouse_motion_event`](../../event/trait.EventHandler.html#method.mouse_motion_event)
+    /// per frame.
+    ///
+    /// When enabled, the event loop doesn't call `mouse_motion_event` for every
+    /// cursor movement `winit` reports, but once per frame right before `update`,
+    /// with the latest position and the summed up delta of the whole frame.
+    /// This saves a lot of callbacks with high polling rate mice.
+    /// Raw mouse motion is not affected.
+    pub fn set_coalesce_motion(&mut self, coalesce: bool) {
+        self.coalesce_motion = coalesce;
+        self.motion_pending = false;
+    }
+
+    /// Applies the raw sensitivity settings to a raw mouse motion and adds it
+    /// to [`raw_delta()`](#method.raw_delta).
+    ///
+    /// This function is called internally whenever `winit` reports raw mouse motion.
+    /// It can also be used to simulate raw mouse input.
+    /// Calling this function alone won't trigger a
+    /// [`raw_mouse_motion_event`](../../event/trait.EventHandler.html#method.raw_mouse_motion_event) though.
+    pub fn handle_raw_motion(&mut self, dx: f64, dy: f64) {
+        let mut scaled = glam::DVec2::new(dx, dy) * self.raw_sensitivity;
+        if self.raw_invert_y {
+            scaled.y = -scaled.y;
+        }
+        self.raw_delta += scaled;
+        self.last_raw_delta = scaled;
+    }
+
+    /// Resets the values returned by [`mouse::delta`](fn.delta.html) and
+    /// [`raw_delta`](#method.raw_delta) to zero.
     /// You shouldn't need to call this, except when you're running your own event loop.
-    /// In this case call it right at the end, after `draw` and `update` have finished.
+    /// In this case call it right at the end, after `draw` and `update` have finished,
+    /// or call [`input::end_frame()`](../fn.end_frame.html), which does this for you.
     pub fn reset_delta(&mut self) {
         self.delta = glam::Vec2::ZERO;
+        self.raw_delta = glam::DVec2::ZERO;
     }
 
     /// Copies the current state of the mouse buttons into the context. If you are writing your own event loop
     /// you need to call this at the end of every update in order to use the functions `is_button_just_pressed`
     /// and `is_button_just_released`. Otherwise this is handled for you.
+    /// [`input::end_frame()`](../fn.end_frame.html) calls this along with the keyboard equivalent.
     pub fn save_mouse_state(&mut self) {
         self.previous_buttons_pressed = self.buttons_pressed.clone();
     }
@@ -128,12 +286,39 @@ impl MouseContext {
         self.delta = p;
     }
 
+    pub(crate) fn queue_motion(&mut self) {
+        self.motion_pending = true;
+    }
+
+    /// Returns whether a coalesced motion event is due and clears it.
+    pub(crate) fn take_pending_motion(&mut self) -> bool {
+        std::mem::take(&mut self.motion_pending)
+    }
+
     pub(crate) fn set_button(&mut self, button: MouseButton, pressed: bool) {
         if pressed {
             let _ = self.buttons_pressed.insert(button);
         } else {
             let _ = self.buttons_pressed.remove(&button);
         }
+
+        if button == MouseButton::Left {
+            self.drag_start = if pressed {
+                self.drag_start.or(Some(self.last_position))
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Releases every held mouse button, returning the buttons that were pressed.
+    /// Used when the window loses focus, since the button-up events are sent elsewhere then.
+    pub(crate) fn release_all(&mut self) -> Vec<MouseButton> {
+        let buttons: Vec<MouseButton> = self.buttons_pressed.iter().copied().collect();
+        for &button in &buttons {
+            self.set_button(button, false);
+        }
+        buttons
     }
 
     /// Get the distance the cursor was moved between the latest two `mouse_motion_events`.
@@ -141,6 +326,12 @@ impl MouseContext {
     pub fn last_delta(&self) -> mint::Point2<f32> {
         self.last_delta.into()
     }
+
+    /// Get the scaled raw motion reported by the latest `raw_mouse_motion_event`.
+    /// Really useful only if you are writing your own event loop
+    pub fn last_raw_delta(&self) -> mint::Vector2<f64> {
+        self.last_raw_delta.into()
+    }
 }
 
 impl Default for MouseContext {
@@ -218,10 +409,9 @@ pub fn set_cursor_hidden(ctx: &mut Context, hidden: bool) {
 }
 
 /// Modifies the mouse cursor type of the window.
-// TODO: Move to graphics context (This isn't input)
+#[deprecated(since = "0.9.4", note = "Use `ctx.mouse.set_cursor_type` instead")]
 pub fn set_cursor_type(ctx: &mut Context, cursor_type: CursorIcon) {
-    ctx.mouse.cursor_type = cursor_type;
-    ctx.gfx.window.set_cursor_icon(cursor_type);
+    ctx.mouse.set_cursor_type(&ctx.gfx, cursor_type);
 }
 
 /// Get whether or not the mouse is grabbed.
@@ -253,21 +443,155 @@ pub fn set_cursor_grabbed(ctx: &mut Context, grabbed: bool) -> GameResult {
 
 /// Set the current position of the mouse cursor, in pixels.
 /// Uses strictly window-only coordinates.
+///
+/// Since 0.9.4 the position is in physical pixels, like the one returned by
+/// [`MouseContext::position()`](struct.MouseContext.html#method.position);
+/// it used to be in logical pixels, which differ on HiDPI displays.
+///
 /// ### Errors
 ///
 /// Will return `GameError::WindowError` if platform doesn't support this.
-// TODO: Move to graphics context (This isn't input)
+#[deprecated(since = "0.9.4", note = "Use `ctx.mouse.set_position` instead")]
 pub fn set_position<P>(ctx: &mut Context, point: P) -> GameResult
 where
     P: Into<mint::Point2<f32>>,
 {
-    let point = glam::Vec2::from(point.into());
-    ctx.mouse.last_position = point;
-    ctx.gfx
-        .window
-        .set_cursor_position(dpi::LogicalPosition {
-            x: f64::from(point.x),
-            y: f64::from(point.y),
-        })
-        .map_err(|_| GameError::WindowError("Couldn't set mouse cursor position!".to_owned()))
+    let point = point.into();
+    ctx.mouse.set_position(&ctx.gfx, point.x, point.y)
+}
+
+/// Maps a position in physical pixels of a `width` x `height` drawable onto `screen`.
+fn to_screen_coordinates(
+    position: glam::Vec2,
+    width: f32,
+    height: f32,
+    screen: Rect,
+) -> glam::Vec2 {
+    if width <= 0.0 || height <= 0.0 {
+        return glam::Vec2::new(screen.x, screen.y);
+    }
+    glam::Vec2::new(
+        screen.x + position.x / width * screen.w,
+        screen.y + position.y / height * screen.h,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pressed_buttons_tracking() {
+        let mut mouse = MouseContext::new();
+        assert!(!mouse.button_pressed(MouseButton::Left));
+        mouse.set_button(MouseButton::Left, true);
+        assert!(mouse.button_pressed(MouseButton::Left));
+        assert!(mouse.button_just_pressed(MouseButton::Left));
+        mouse.save_mouse_state();
+        assert!(mouse.button_pressed(MouseButton::Left));
+        assert!(!mouse.button_just_pressed(MouseButton::Left));
+        mouse.set_button(MouseButton::Left, false);
+        assert!(!mouse.button_pressed(MouseButton::Left));
+        assert!(mouse.button_just_released(MouseButton::Left));
+        mouse.save_mouse_state();
+        assert!(!mouse.button_just_released(MouseButton::Left));
+    }
+
+    #[test]
+    fn release_all_buttons() {
+        let mut mouse = MouseContext::new();
+        mouse.set_button(MouseButton::Left, true);
+        mouse.set_button(MouseButton::Right, true);
+        let mut released = mouse.release_all();
+        released.sort_by_key(|b| format!("{b:?}"));
+        assert_eq!(released, vec![MouseButton::Left, MouseButton::Right]);
+        assert!(!mouse.button_pressed(MouseButton::Left));
+        assert!(!mouse.button_pressed(MouseButton::Right));
+        assert!(mouse.release_all().is_empty());
+    }
+
+    #[test]
+    fn screen_coordinate_mapping() {
+        // an 800x600 window showing 320x180 game pixels, letterboxed vertically
+        let screen = Rect::new(0.0, -30.0, 320.0, 240.0);
+        let map = |x, y| to_screen_coordinates(glam::Vec2::new(x, y), 800.0, 600.0, screen);
+        assert_eq!(map(0.0, 75.0), glam::Vec2::new(0.0, 0.0));
+        assert_eq!(map(400.0, 300.0), glam::Vec2::new(160.0, 90.0));
+        assert_eq!(map(800.0, 525.0), glam::Vec2::new(320.0, 180.0));
+        assert_eq!(
+            to_screen_coordinates(glam::Vec2::new(5.0, 5.0), 0.0, 0.0, screen),
+            glam::Vec2::new(0.0, -30.0)
+        );
+    }
+
+    #[test]
+    fn extra_buttons_tracking() {
+        let mut mouse = MouseContext::new();
+        let back = MouseButton::Other(8);
+        let forward = MouseButton::Other(9);
+        mouse.set_button(back, true);
+        assert!(mouse.button_pressed(back));
+        assert!(mouse.button_just_pressed(back));
+        assert!(!mouse.button_pressed(forward));
+        assert!(!mouse.button_pressed(MouseButton::Left));
+        mouse.save_mouse_state();
+        mouse.set_button(forward, true);
+        assert!(!mouse.button_just_pressed(back));
+        assert!(mouse.button_just_pressed(forward));
+        mouse.set_button(back, false);
+        assert!(mouse.button_just_released(back));
+        assert!(mouse.button_pressed(forward));
+    }
+
+    #[test]
+    fn drag_tracking() {
+        let mut mouse = MouseContext::new();
+        mouse.handle_move(10.0, 20.0);
+        assert_eq!(mouse.drag_start(), None);
+        assert_eq!(mouse.drag_delta(), None);
+        mouse.set_button(MouseButton::Left, true);
+        assert_eq!(mouse.drag_start(), Some([10.0, 20.0].into()));
+        assert_eq!(mouse.drag_delta(), Some([0.0, 0.0].into()));
+        mouse.handle_move(15.0, 12.0);
+        mouse.save_mouse_state();
+        mouse.handle_move(25.0, 10.0);
+        assert_eq!(mouse.drag_start(), Some([10.0, 20.0].into()));
+        assert_eq!(mouse.drag_delta(), Some([15.0, -10.0].into()));
+        mouse.set_button(MouseButton::Right, true);
+        assert_eq!(mouse.drag_start(), Some([10.0, 20.0].into()));
+        mouse.set_button(MouseButton::Left, false);
+        assert_eq!(mouse.drag_start(), None);
+        assert_eq!(mouse.drag_delta(), None);
+    }
+
+    #[test]
+    fn raw_motion_scaling() {
+        let mut mouse = MouseContext::new();
+        mouse.handle_raw_motion(1.0, 2.0);
+        assert_eq!(mouse.last_raw_delta(), [1.0, 2.0].into());
+        mouse.set_raw_sensitivity(2.0, 0.5);
+        mouse.handle_raw_motion(1.0, 2.0);
+        assert_eq!(mouse.last_raw_delta(), [2.0, 1.0].into());
+        mouse.set_raw_invert_y(true);
+        mouse.handle_raw_motion(1.0, 2.0);
+        assert_eq!(mouse.last_raw_delta(), [2.0, -1.0].into());
+        assert_eq!(mouse.raw_delta(), [5.0, 2.0].into());
+        mouse.reset_delta();
+        assert_eq!(mouse.raw_delta(), [0.0, 0.0].into());
+    }
+
+    #[test]
+    fn coalesced_motion() {
+        let mut mouse = MouseContext::new();
+        mouse.set_coalesce_motion(true);
+        assert!(!mouse.take_pending_motion());
+        mouse.handle_move(2.0, 1.0);
+        mouse.queue_motion();
+        mouse.handle_move(5.0, 3.0);
+        mouse.queue_motion();
+        assert!(mouse.take_pending_motion());
+        assert!(!mouse.take_pending_motion());
+        assert_eq!(mouse.position(), mint::Point2 { x: 5.0, y: 3.0 });
+        assert_eq!(mouse.delta(), mint::Point2 { x: 5.0, y: 3.0 });
+    }
 }
@@ -81,6 +81,13 @@ where
     fn latest(&self) -> T {
         self.contents[self.head]
     }
+
+    /// Returns the `n` most recent values in the buffer, newest first.
+    /// At most as many values as the buffer holds are returned.
+    fn latest_n(&self, n: usize) -> impl Iterator<Item = T> + '_ {
+        let len = self.contents.len();
+        (0..n.min(len)).map(move |i| self.contents[(self.head + len - i) % len])
+    }
 }
 
 /// A structure that contains our time-tracking state.
@@ -91,6 +98,10 @@ pub struct TimeContext {
     frame_durations: LogBuffer<time::Duration>,
     residual_update_dt: time::Duration,
     frame_count: usize,
+    scheduled: Vec<(time::Instant, u64)>,
+    max_delta: Option<time::Duration>,
+    max_update_rate: Option<f32>,
+    dropped_frames_checked: usize,
 }
 
 /// How many frames we log update times for.
@@ -106,6 +117,10 @@ impl TimeContext {
             frame_durations: LogBuffer::new(TIME_LOG_FRAMES, initial_dt),
             residual_update_dt: time::Duration::from_secs(0),
             frame_count: 0,
+            scheduled: Vec::new(),
+            max_delta: None,
+            max_update_rate: None,
+            dropped_frames_checked: 0,
         }
     }
 
@@ -115,6 +130,53 @@ impl TimeContext {
         self.frame_durations.latest()
     }
 
+    /// Returns the longest frame time [`tick()`](#method.tick) will record, if any.
+    pub fn max_delta(&self) -> Option<time::Duration> {
+        self.max_delta
+    }
+
+    /// Sets the longest frame time [`tick()`](#method.tick) will record.
+    ///
+    /// Longer frames, like after sitting on a breakpoint or while the window is
+    /// being dragged, are reported as taking exactly `max_delta`. This applies to
+    /// [`delta()`](#method.delta) as well as to [`check_update_time()`](#method.check_update_time),
+    /// so a single long frame doesn't cause a burst of catch-up updates.
+    /// Pass `None` to disable the limit, which is the default.
+    ///
+    /// [`time_since_start()`](#method.time_since_start) is not affected.
+    pub fn set_max_delta(&mut self, max_delta: impl Into<Option<time::Duration>>) {
+        self.max_delta = max_delta.into();
+    }
+
+    /// Returns the most updates per second [`event::run()`](../event/fn.run.html) will run, if any.
+    pub fn max_update_rate(&self) -> Option<f32> {
+        self.max_update_rate
+    }
+
+    /// Caps how many times per second [`event::run()`](../event/fn.run.html) runs a frame,
+    /// by sleeping until enough time has passed since the previous one.
+    ///
+    /// Without vsync, or without a window at all, the loop otherwise runs as fast as it
+    /// can, burning power and having [`check_update_time()`](#method.check_update_time)
+    /// hand out tiny steps. Rates that aren't positive and finite, or `None`, disable
+    /// the limit, which is the default.
+    pub fn set_max_update_rate(&mut self, rate: Option<f32>) {
+        self.max_update_rate = rate.filter(|rate| rate.is_finite() && *rate > 0.0);
+    }
+
+    /// Returns how long to wait before the next frame to respect the
+    /// [`max_update_rate()`](#method.max_update_rate), or zero if there is no need to wait.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// [`event::run()`](../event/fn.run.html) will do it for you.
+    pub fn time_until_next_update(&self) -> time::Duration {
+        match self.max_update_rate {
+            Some(rate) => time::Duration::from_secs_f32(1.0 / rate)
+                .saturating_sub(self.last_instant.elapsed()),
+            None => time::Duration::ZERO,
+        }
+    }
+
     /// Gets the average time of a frame, averaged
     /// over the last 200 frames.
     pub fn average_delta(&self) -> time::Duration {
@@ -130,6 +192,24 @@ impl TimeContext {
         }
     }
 
+    /// Returns how many frames took longer than 1.5 times the frame time of `target_fps`
+    /// since the last call, for a hitch counter in a performance overlay.
+    ///
+    /// Only the last 200 frames are remembered, so call this at least that often
+    /// to not miss any.
+    pub fn dropped_frames(&mut self, target_fps: f32) -> u32 {
+        let new_frames = self.frame_count - self.dropped_frames_checked;
+        self.dropped_frames_checked = self.frame_count;
+        if !(target_fps.is_finite() && target_fps > 0.0) {
+            return 0;
+        }
+        let threshold = time::Duration::from_secs_f32(1.5 / target_fps);
+        self.frame_durations
+            .latest_n(new_frames)
+            .filter(|&duration| duration > threshold)
+            .count() as u32
+    }
+
     /// Gets the FPS of the game, averaged over the last
     /// 200 frames.
     pub fn fps(&self) -> f64 {
@@ -138,6 +218,14 @@ impl TimeContext {
         1.0 / seconds_per_frame
     }
 
+    /// Writes the timing section of [`Context::debug_dump()`](crate::Context::debug_dump).
+    pub(crate) fn write_debug_dump(&self, out: &mut impl std::fmt::Write) -> std::fmt::Result {
+        writeln!(out, "[time]")?;
+        writeln!(out, "  fps: {:.1}", self.fps())?;
+        writeln!(out, "  average delta: {:?}", self.average_delta())?;
+        writeln!(out, "  ticks: {}", self.ticks())
+    }
+
     /// Gets the number of times the game has gone through its event loop.
     ///
     /// Specifically, the number of times that [`TimeContext::tick()`](struct.TimeContext.html#method.tick)
@@ -148,6 +236,9 @@ impl TimeContext {
 
     /// Returns the time since the game was initialized,
     /// as reported by the system clock.
+    ///
+    /// This is measured directly rather than summed up from frame deltas,
+    /// so it doesn't drift and is fine to use for things like total playtime.
     pub fn time_since_start(&self) -> time::Duration {
         self.init_instant.elapsed()
     }
@@ -195,6 +286,44 @@ impl TimeContext {
         self.residual_update_dt
     }
 
+    /// Schedules a [`scheduled_event`](../event/trait.EventHandler.html#method.scheduled_event)
+    /// with the given `id` to fire once `delay` has elapsed.
+    ///
+    /// The delay is checked once per frame, against the time recorded by
+    /// [`tick()`](#method.tick), so the event fires on the first frame at or after
+    /// the deadline. Scheduling the same `id` multiple times results in multiple events.
+    pub fn after(&mut self, delay: time::Duration, id: u64) {
+        self.scheduled.push((self.last_instant + delay, id));
+    }
+
+    /// Cancels all pending scheduled events with the given `id`.
+    ///
+    /// Returns whether or not there was anything to cancel.
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let len = self.scheduled.len();
+        self.scheduled
+            .retain(|&(_, scheduled_id)| scheduled_id != id);
+        self.scheduled.len() != len
+    }
+
+    /// Returns the `id` of a scheduled event whose deadline has passed, removing it.
+    /// Events are returned in the order of their deadlines.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// [`event::run()`](../event/fn.run.html) will do it for you.
+    /// You only need to call this function if you're writing your
+    /// own custom event loop.
+    pub fn next_scheduled_event(&mut self) -> Option<u64> {
+        let now = self.last_instant;
+        let (index, _) = self
+            .scheduled
+            .iter()
+            .enumerate()
+            .filter(|(_, (deadline, _))| *deadline <= now)
+            .min_by_key(|(_, (deadline, _))| *deadline)?;
+        Some(self.scheduled.remove(index).1)
+    }
+
     /// Update the state of the `TimeContext` to record that
     /// another frame has taken place.  Necessary for the FPS
     /// tracking and [`check_update_time()`](fn.check_update_time.html)
@@ -206,7 +335,10 @@ impl TimeContext {
     /// own custom event loop.
     pub fn tick(&mut self) {
         let now = time::Instant::now();
-        let time_since_last = now - self.last_instant;
+        let mut time_since_last = now - self.last_instant;
+        if let Some(max_delta) = self.max_delta {
+            time_since_last = time_since_last.min(max_delta);
+        }
         self.frame_durations.push(time_since_last);
         self.last_instant = now;
         self.frame_count += 1;
@@ -329,3 +461,77 @@ pub fn yield_now() {
 pub fn ticks(ctx: &Context) -> usize {
     ctx.time.frame_count
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduled_events() {
+        let mut timer = TimeContext::new();
+        timer.after(time::Duration::from_secs(3600), 1);
+        timer.after(time::Duration::ZERO, 2);
+        timer.after(time::Duration::ZERO, 3);
+        assert!(timer.cancel(3));
+        assert!(!timer.cancel(3));
+        timer.tick();
+        assert_eq!(timer.next_scheduled_event(), Some(2));
+        assert_eq!(timer.next_scheduled_event(), None);
+        assert!(timer.cancel(1));
+        assert_eq!(timer.next_scheduled_event(), None);
+    }
+
+    #[test]
+    fn ticks_and_elapsed_time() {
+        let mut timer = TimeContext::new();
+        assert_eq!(timer.ticks(), 0);
+        let before = timer.time_since_start();
+        for _ in 0..3 {
+            timer.tick();
+        }
+        assert_eq!(timer.ticks(), 3);
+        assert!(timer.time_since_start() >= before);
+    }
+
+    #[test]
+    fn max_delta_clamps_long_frames() {
+        let mut timer = TimeContext::new();
+        let max_delta = time::Duration::from_millis(1);
+        timer.set_max_delta(max_delta);
+        std::thread::sleep(time::Duration::from_millis(5));
+        timer.tick();
+        assert_eq!(timer.delta(), max_delta);
+        assert_eq!(timer.remaining_update_time(), max_delta);
+        timer.set_max_delta(None);
+        assert_eq!(timer.max_delta(), None);
+    }
+
+    #[test]
+    fn dropped_frames_since_last_query() {
+        let mut timer = TimeContext::new();
+        for ms in [16, 40, 16, 30] {
+            timer.frame_durations.push(time::Duration::from_millis(ms));
+            timer.frame_count += 1;
+        }
+        assert_eq!(timer.dropped_frames(60.0), 2);
+        assert_eq!(timer.dropped_frames(60.0), 0);
+        timer.frame_durations.push(time::Duration::from_millis(50));
+        timer.frame_count += 1;
+        assert_eq!(timer.dropped_frames(30.0), 0);
+        timer.frame_durations.push(time::Duration::from_millis(50));
+        timer.frame_count += 1;
+        assert_eq!(timer.dropped_frames(60.0), 1);
+    }
+
+    #[test]
+    fn max_update_rate_waits() {
+        let mut timer = TimeContext::new();
+        assert_eq!(timer.time_until_next_update(), time::Duration::ZERO);
+        timer.set_max_update_rate(Some(0.0));
+        assert_eq!(timer.max_update_rate(), None);
+        timer.set_max_update_rate(Some(1.0));
+        timer.tick();
+        let wait = timer.time_until_next_update();
+        assert!(wait > time::Duration::ZERO && wait <= time::Duration::from_secs(1));
+    }
+}
@@ -448,6 +448,19 @@ impl OverlayFS {
     pub fn roots(&self) -> &VecDeque<Box<dyn VFS>> {
         &self.roots
     }
+
+    /// Returns the location of the file at this path on disk, if it is
+    /// a plain file in a physical directory rather than inside a zip file.
+    #[cfg(feature = "audio")]
+    pub fn physical_path(&self, path: &Path) -> Option<PathBuf> {
+        let vfs = self.roots.iter().find(|vfs| vfs.exists(path))?;
+        let full_path = vfs.to_path_buf()?.join(sanitize_path(path)?);
+        if full_path.is_file() {
+            Some(full_path)
+        } else {
+            None
+        }
+    }
 }
 
 impl VFS for OverlayFS {
@@ -0,0 +1,72 @@
+//! Tests for `TestDriver`. These need a window, and only one event loop can be created
+//! per process, so everything is checked in a single test in a binary of its own.
+//! Run with `cargo test --test driver -- --test-threads=1`.
+
+use ggez::{
+    conf::WindowMode,
+    event::{EventHandler, TestDriver},
+    input::keyboard::{KeyCode, KeyInput},
+    Context, ContextBuilder, GameError, GameResult,
+};
+
+#[derive(Default)]
+struct Game {
+    updates: u32,
+    draws: u32,
+    fail_draw: bool,
+    keys_down: Vec<KeyInput>,
+}
+
+impl EventHandler for Game {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        self.updates += 1;
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let canvas = ggez::graphics::Canvas::from_frame(ctx, None);
+        canvas.finish(ctx)?;
+        if self.fail_draw {
+            return Err(GameError::CustomError(String::from("draw failed")));
+        }
+        self.draws += 1;
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
+        self.keys_down.push(input);
+        Ok(())
+    }
+}
+
+#[test]
+fn driver() {
+    let (ctx, _event_loop) = ContextBuilder::new("driver", "ggez")
+        .window_mode(WindowMode::default().visible(false))
+        .build()
+        .unwrap();
+    let mut driver = TestDriver::new(ctx, Game::default());
+
+    // every key gets a scancode of its own, so releasing one doesn't release the other
+    driver.press_key(KeyCode::A).unwrap();
+    driver.press_key(KeyCode::B).unwrap();
+    let [a, b] = [driver.state().keys_down[0], driver.state().keys_down[1]];
+    assert_ne!(a.scancode, b.scancode);
+    driver.release_key(KeyCode::A).unwrap();
+    driver.assert_key_pressed(KeyCode::B);
+    assert!(!driver.ctx().keyboard.is_scancode_pressed(a.scancode));
+    assert!(driver.ctx().keyboard.is_scancode_pressed(b.scancode));
+
+    driver.move_mouse(10.0, 20.0).unwrap();
+    driver.assert_mouse_position(10.0, 20.0);
+
+    driver.tick().unwrap();
+    assert_eq!((driver.state().updates, driver.state().draws), (1, 1));
+
+    // a failed draw is returned, but the frame is still ended so the next one can be drawn
+    driver.state_mut().fail_draw = true;
+    assert!(driver.tick().is_err());
+    driver.state_mut().fail_draw = false;
+    driver.tick().unwrap();
+    assert_eq!((driver.state().updates, driver.state().draws), (3, 2));
+}